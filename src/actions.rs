@@ -1,16 +1,13 @@
-use rand::seq::SliceRandom;
-use rand::thread_rng;
 use crate::*;
 
 use nalgebra::Vector3;
 
 
-pub fn shuffle_splats(splats: &mut Vec<UberSplat>) {
-    let mut rng = thread_rng();
-    splats.shuffle(&mut rng);
+pub fn shuffle_splats(splats: &mut [UberSplat], rng: &mut SplatRng) {
+    shuffle_slice(splats, rng);
 }
 
-pub fn find_center(splats: &Vec<UberSplat>) -> Vector3<f32> {
+pub fn find_center(splats: &[UberSplat]) -> Vector3<f32> {
     let mut center = Vector3::new(0.0, 0.0, 0.0);
     for splat in splats {
         center += splat.position;
@@ -18,13 +15,23 @@ pub fn find_center(splats: &Vec<UberSplat>) -> Vector3<f32> {
     center / splats.len() as f32
 }
 
-pub fn move_splats(splats: &mut Vec<UberSplat>, offset: Vector3<f32>) {
+pub fn find_bounds(splats: &[UberSplat]) -> (Vector3<f32>, Vector3<f32>) {
+    let mut min = Vector3::repeat(f32::INFINITY);
+    let mut max = Vector3::repeat(f32::NEG_INFINITY);
+    for splat in splats {
+        min = min.inf(&splat.position);
+        max = max.sup(&splat.position);
+    }
+    (min, max)
+}
+
+pub fn move_splats(splats: &mut [UberSplat], offset: Vector3<f32>) {
     for splat in splats {
         splat.position += offset;
     }
 }
 
-pub fn center_splats(splats: &mut Vec<UberSplat>) {
+pub fn center_splats(splats: &mut [UberSplat]) {
     let center = find_center(splats);
     let offset = -center;
     move_splats(splats, offset);
@@ -0,0 +1,117 @@
+use crate::*;
+use nalgebra::Matrix3;
+
+#[derive(Debug, Clone)]
+pub struct AdjustOptions {
+    /// Factor applied to linear opacities.
+    pub opacity_gain: f32,
+    /// Exponent applied to linear opacities before the gain; values below 1 boost faint splats.
+    pub opacity_curve: f32,
+    /// Smallest allowed linear scale.
+    pub min_scale: Option<f32>,
+    /// Largest allowed linear scale.
+    pub max_scale: Option<f32>,
+    /// Standard deviation of the Mip-Splatting 3D filter in world units.
+    pub filter_3d: Option<f32>,
+}
+
+impl Default for AdjustOptions {
+    fn default() -> Self {
+        AdjustOptions {
+            opacity_gain: 1.0,
+            opacity_curve: 1.0,
+            min_scale: None,
+            max_scale: None,
+            filter_3d: None,
+        }
+    }
+}
+
+fn covariance(splat: &UberSplat) -> Matrix3<f32> {
+    let (a, b) = splat.to_cov();
+    Matrix3::new(a.x, a.y, a.z, a.y, b.x, b.y, a.z, b.y, b.z)
+}
+
+/// Convolve the splat with an isotropic gaussian of standard deviation `size`, as the Mip-Splatting 3D filter does.
+///
+/// The covariance gains `size²` on its diagonal, which keeps the splat's axes, and the opacity is scaled by the ratio
+/// of the determinants so the splat's total weight is unchanged.
+fn filter_3d(splat: &mut UberSplat, size: f32) {
+    let variance = size * size;
+    let before = covariance(splat).determinant().max(0.0);
+    let after = (covariance(splat) + Matrix3::from_diagonal_element(variance)).determinant();
+    let scale = splat
+        .scale
+        .to_linear_float()
+        .map(|s| (s * s + variance).sqrt());
+    splat.scale = splat.scale.with_linear(scale);
+    let opacity = splat.opacity.to_linear_float() * (before / after).sqrt();
+    splat.opacity = Opacity::LinearFloat(opacity);
+}
+
+/// Apply the 3D filter, then the scale clamps, then the opacity curve and gain. Returns the number of splats changed.
+pub fn adjust_splats(splats: &mut [UberSplat], options: &AdjustOptions) -> usize {
+    let mut changed = 0;
+    for splat in splats.iter_mut() {
+        let before = (
+            splat.scale.to_linear_float(),
+            splat.opacity.to_linear_float(),
+        );
+        if let Some(size) = options.filter_3d {
+            filter_3d(splat, size);
+        }
+        let min = options.min_scale.unwrap_or(0.0);
+        let max = options.max_scale.unwrap_or(f32::INFINITY);
+        if let Some(scale) = clamp_scale(&splat.scale, min, max) {
+            splat.scale = scale;
+        }
+        if options.opacity_gain != 1.0 || options.opacity_curve != 1.0 {
+            let opacity = splat.opacity.to_linear_float().powf(options.opacity_curve);
+            splat.opacity = Opacity::LinearFloat((opacity * options.opacity_gain).clamp(0.0, 1.0));
+        }
+        if (
+            splat.scale.to_linear_float(),
+            splat.opacity.to_linear_float(),
+        ) != before
+        {
+            changed += 1;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+#[test]
+fn test_adjust_splats() {
+    let splat = UberSplat::new(
+        nalgebra::Vector3::zeros(),
+        None,
+        Color::LinearFloat(nalgebra::Vector3::repeat(0.5)),
+        Opacity::LogitFloat(0.0),
+        Scale::Exponent(nalgebra::Vector3::new(0.1_f32, 0.2, 0.02).map(f32::ln)),
+        nalgebra::Quaternion::new(0.9, 0.1, -0.3, 0.2),
+    );
+    let mut filtered = [splat.clone()];
+    let options = AdjustOptions {
+        filter_3d: Some(0.05),
+        ..Default::default()
+    };
+    assert_eq!(adjust_splats(&mut filtered, &options), 1);
+    let expected = covariance(&splat) + Matrix3::from_diagonal_element(0.0025);
+    assert!((covariance(&filtered[0]) - expected).norm() < 1e-6);
+    let ratio = (covariance(&splat).determinant() / expected.determinant()).sqrt();
+    assert!((filtered[0].opacity.to_linear_float() - 0.5 * ratio).abs() < 1e-5);
+    assert!(matches!(filtered[0].scale, Scale::Exponent(_)));
+
+    let mut clamped = [splat.clone()];
+    let options = AdjustOptions {
+        opacity_gain: 1.5,
+        opacity_curve: 0.5,
+        max_scale: Some(0.15),
+        ..Default::default()
+    };
+    adjust_splats(&mut clamped, &options);
+    assert!((clamped[0].scale.to_linear_float().y - 0.15).abs() < 1e-6);
+    assert_eq!(clamped[0].opacity.to_linear_float(), 1.0);
+    assert_eq!(adjust_splats(&mut clamped, &AdjustOptions::default()), 0);
+}
@@ -0,0 +1,153 @@
+use anyhow::Result;
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A camera pose at a point in time, as read from a camera path JSON file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraKeyframe {
+    pub time: f32,
+    pub eye: Vector3<f32>,
+    pub target: Vector3<f32>,
+    /// Vertical field of view in degrees; falls back to the command line value.
+    pub fov: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraPath {
+    pub keyframes: Vec<CameraKeyframe>,
+}
+
+/// An eye, target and field of view for one frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraPose {
+    pub eye: Vector3<f32>,
+    pub target: Vector3<f32>,
+    pub fov: f32,
+}
+
+/// `frames` poses evenly spaced on a circle around `center`, `elevation` degrees above the plane perpendicular to
+/// `up`.
+pub fn orbit_poses(
+    center: &Vector3<f32>,
+    up: &Vector3<f32>,
+    radius: f32,
+    elevation: f32,
+    frames: usize,
+    fov: f32,
+) -> Vec<CameraPose> {
+    let up = up.normalize();
+    let reference = if up.x.abs() < 0.9 {
+        Vector3::x()
+    } else {
+        Vector3::z()
+    };
+    let right = (reference - up * reference.dot(&up)).normalize();
+    let forward = up.cross(&right);
+    let elevation = elevation.to_radians();
+    (0..frames)
+        .map(|frame| {
+            let angle = std::f32::consts::TAU * frame as f32 / frames as f32;
+            let horizontal = right * angle.cos() + forward * angle.sin();
+            let offset = (horizontal * elevation.cos() + up * elevation.sin()) * radius;
+            CameraPose {
+                eye: center + offset,
+                target: *center,
+                fov,
+            }
+        })
+        .collect()
+}
+
+fn catmull_rom(
+    p0: &Vector3<f32>,
+    p1: &Vector3<f32>,
+    p2: &Vector3<f32>,
+    p3: &Vector3<f32>,
+    t: f32,
+) -> Vector3<f32> {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p3 - p0 + (p1 - p2) * 3.0) * t3)
+        * 0.5
+}
+
+impl CameraPath {
+    pub fn load(path: &Path) -> Result<CameraPath> {
+        let json = std::fs::read_to_string(path)?;
+        let mut camera_path: CameraPath = serde_json::from_str(&json)?;
+        if camera_path.keyframes.is_empty() {
+            anyhow::bail!("Camera path has no keyframes");
+        }
+        camera_path
+            .keyframes
+            .sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(camera_path)
+    }
+
+    /// The pose at `time`, interpolating positions with a Catmull-Rom spline and field of view linearly.
+    pub fn pose_at(&self, time: f32, default_fov: f32) -> CameraPose {
+        let keyframes = &self.keyframes;
+        let last = keyframes.len() - 1;
+        let index = keyframes
+            .iter()
+            .rposition(|k| k.time <= time)
+            .unwrap_or(0)
+            .min(last.saturating_sub(1));
+        let (k1, k2) = (&keyframes[index], &keyframes[(index + 1).min(last)]);
+        let (k0, k3) = (
+            &keyframes[index.saturating_sub(1)],
+            &keyframes[(index + 2).min(last)],
+        );
+        let span = k2.time - k1.time;
+        let t = if span > 0.0 {
+            ((time - k1.time) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let fov1 = k1.fov.unwrap_or(default_fov);
+        let fov2 = k2.fov.unwrap_or(default_fov);
+        CameraPose {
+            eye: catmull_rom(&k0.eye, &k1.eye, &k2.eye, &k3.eye, t),
+            target: catmull_rom(&k0.target, &k1.target, &k2.target, &k3.target, t),
+            fov: fov1 + (fov2 - fov1) * t,
+        }
+    }
+
+    /// `frames` poses sampled evenly from the first to the last keyframe, inclusive.
+    pub fn poses(&self, frames: usize, default_fov: f32) -> Vec<CameraPose> {
+        let start = self.keyframes[0].time;
+        let end = self.keyframes[self.keyframes.len() - 1].time;
+        (0..frames)
+            .map(|frame| {
+                let t = if frames > 1 {
+                    frame as f32 / (frames - 1) as f32
+                } else {
+                    0.0
+                };
+                self.pose_at(start + (end - start) * t, default_fov)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_camera_path_passes_through_keyframes() {
+    let keyframe = |time: f32, x: f32| CameraKeyframe {
+        time,
+        eye: Vector3::new(x, 0.0, 5.0),
+        target: Vector3::zeros(),
+        fov: None,
+    };
+    let path = CameraPath {
+        keyframes: vec![keyframe(0.0, 0.0), keyframe(1.0, 1.0), keyframe(3.0, 4.0)],
+    };
+    let poses = path.poses(4, 50.0);
+    assert_eq!(poses[0].eye, Vector3::new(0.0, 0.0, 5.0));
+    assert_eq!(poses[3].eye, Vector3::new(4.0, 0.0, 5.0));
+    assert!((path.pose_at(1.0, 50.0).eye.x - 1.0).abs() < 1e-6);
+}
@@ -0,0 +1,274 @@
+use crate::*;
+use anyhow::{Context, Result};
+use nalgebra::{
+    Isometry3, Matrix3, Quaternion, Rotation3, Translation3, UnitQuaternion, Vector2, Vector3,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A camera from a dataset together with its ground truth image.
+#[derive(Debug, Clone)]
+pub struct DatasetView {
+    pub name: String,
+    pub image_path: PathBuf,
+    pub camera: Camera,
+}
+
+impl DatasetView {
+    /// The camera with its intrinsics scaled to an image of a different size.
+    pub fn camera_for_size(&self, width: usize, height: usize) -> Camera {
+        let scale = Vector2::new(
+            width as f32 / self.camera.width as f32,
+            height as f32 / self.camera.height as f32,
+        );
+        Camera {
+            focal: self.camera.focal.component_mul(&scale),
+            principal_point: self.camera.principal_point.component_mul(&scale),
+            width,
+            height,
+            ..self.camera
+        }
+    }
+}
+
+/// Load views from a COLMAP text model directory or a nerfstudio `transforms.json`.
+///
+/// `images` overrides the directory image names are resolved against.
+pub fn load_dataset(cameras: &Path, images: Option<&Path>) -> Result<Vec<DatasetView>> {
+    let mut views = if cameras.is_dir() {
+        load_colmap(cameras, images)?
+    } else {
+        load_transforms(cameras, images)?
+    };
+    views.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(views)
+}
+
+/// COLMAP looks down +Z with +Y down; the renderer looks down -Z with +Y up.
+fn opencv_to_opengl() -> Isometry3<f32> {
+    Isometry3::from_parts(
+        Translation3::identity(),
+        UnitQuaternion::from_axis_angle(&Vector3::x_axis(), std::f32::consts::PI),
+    )
+}
+
+fn parse_numbers(tokens: &[&str]) -> Result<Vec<f32>> {
+    tokens
+        .iter()
+        .map(|t| {
+            t.parse::<f32>()
+                .with_context(|| format!("Invalid number {t}"))
+        })
+        .collect()
+}
+
+/// Data lines of a COLMAP text file, with comments removed. Empty lines are kept since `images.txt` uses them for
+/// images without points.
+fn colmap_lines(path: &Path) -> Result<Vec<String>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(text
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| line.trim().to_string())
+        .collect())
+}
+
+/// Load `cameras.txt` and `images.txt`. Lens distortion parameters are ignored.
+pub fn load_colmap(directory: &Path, images: Option<&Path>) -> Result<Vec<DatasetView>> {
+    let mut cameras = HashMap::new();
+    for line in colmap_lines(&directory.join("cameras.txt"))? {
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        if tokens.is_empty() {
+            continue;
+        }
+        if tokens.len() < 5 {
+            anyhow::bail!("Invalid camera line: {line}");
+        }
+        let (width, height) = (tokens[2].parse::<usize>()?, tokens[3].parse::<usize>()?);
+        let params = parse_numbers(&tokens[4..])?;
+        let (focal, principal_point) = match tokens[1] {
+            "SIMPLE_PINHOLE" | "SIMPLE_RADIAL" | "RADIAL" if params.len() >= 3 => (
+                Vector2::new(params[0], params[0]),
+                Vector2::new(params[1], params[2]),
+            ),
+            "PINHOLE" | "OPENCV" | "FULL_OPENCV" if params.len() >= 4 => (
+                Vector2::new(params[0], params[1]),
+                Vector2::new(params[2], params[3]),
+            ),
+            model => anyhow::bail!("Unsupported COLMAP camera model {model}"),
+        };
+        cameras.insert(
+            tokens[0].to_string(),
+            Camera {
+                view: Isometry3::identity(),
+                focal,
+                principal_point,
+                width,
+                height,
+            },
+        );
+    }
+
+    let image_directory = match images {
+        Some(images) => images.to_path_buf(),
+        // COLMAP models usually live in `sparse/0` next to an `images` directory.
+        None => directory
+            .ancestors()
+            .map(|d| d.join("images"))
+            .find(|d| d.is_dir())
+            .unwrap_or_else(|| directory.to_path_buf()),
+    };
+
+    let mut views = Vec::new();
+    let lines = colmap_lines(&directory.join("images.txt"))?;
+    let mut lines = lines.iter().skip_while(|line| line.is_empty());
+    while let Some(line) = lines.next() {
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        if tokens.len() < 10 {
+            anyhow::bail!("Invalid image line: {line}");
+        }
+        // Every image line is followed by a line of 2D points, which may be empty.
+        lines.next();
+        let values = parse_numbers(&tokens[1..8])?;
+        let rotation = UnitQuaternion::from_quaternion(Quaternion::new(
+            values[0], values[1], values[2], values[3],
+        ));
+        let translation = Translation3::new(values[4], values[5], values[6]);
+        let camera = cameras
+            .get(tokens[8])
+            .with_context(|| format!("Unknown camera {}", tokens[8]))?;
+        let name = tokens[9..].join(" ");
+        views.push(DatasetView {
+            image_path: image_directory.join(&name),
+            name,
+            camera: Camera {
+                view: opencv_to_opengl() * Isometry3::from_parts(translation, rotation),
+                ..*camera
+            },
+        });
+    }
+    Ok(views)
+}
+
+fn number(value: &Value, key: &str) -> Option<f32> {
+    value.get(key).and_then(Value::as_f64).map(|v| v as f32)
+}
+
+/// Load a nerfstudio or instant-ngp style `transforms.json`. Intrinsics may be given at the top level or per frame.
+pub fn load_transforms(path: &Path, images: Option<&Path>) -> Result<Vec<DatasetView>> {
+    let json: Value = serde_json::from_str(
+        &std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?,
+    )?;
+    let base = images
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| path.parent().unwrap_or(Path::new(".")).to_path_buf());
+    let frames = json
+        .get("frames")
+        .and_then(Value::as_array)
+        .context("transforms.json has no frames")?;
+
+    let mut views = Vec::new();
+    for frame in frames {
+        let get = |key: &str| number(frame, key).or_else(|| number(&json, key));
+        let file_path = frame
+            .get("file_path")
+            .and_then(Value::as_str)
+            .context("Frame has no file_path")?;
+        let mut image_path = base.join(file_path);
+        if image_path.extension().is_none() {
+            image_path.set_extension("png");
+        }
+
+        let (width, height) = match (get("w"), get("h")) {
+            (Some(w), Some(h)) => (w as usize, h as usize),
+            _ => {
+                let image = load_image(&image_path, &Vector3::zeros())?;
+                (image.width, image.height)
+            }
+        };
+        let focal_x = match (get("fl_x"), get("camera_angle_x")) {
+            (Some(focal), _) => focal,
+            (None, Some(angle)) => width as f32 / 2.0 / (angle / 2.0).tan(),
+            _ => anyhow::bail!("Frame {file_path} has no focal length"),
+        };
+        let focal = Vector2::new(focal_x, get("fl_y").unwrap_or(focal_x));
+        let principal_point = Vector2::new(
+            get("cx").unwrap_or(width as f32 / 2.0),
+            get("cy").unwrap_or(height as f32 / 2.0),
+        );
+
+        let rows = frame
+            .get("transform_matrix")
+            .and_then(Value::as_array)
+            .context("Frame has no transform_matrix")?;
+        let element = |row: usize, column: usize| {
+            rows.get(row)
+                .and_then(|r| r.get(column))
+                .and_then(Value::as_f64)
+                .map(|v| v as f32)
+                .context("Invalid transform_matrix")
+        };
+        let mut matrix = Matrix3::zeros();
+        let mut translation = Vector3::zeros();
+        for row in 0..3 {
+            for column in 0..3 {
+                matrix[(row, column)] = element(row, column)?;
+            }
+            translation[row] = element(row, 3)?;
+        }
+        // The rotation part may carry a scale, so re-orthonormalise it.
+        let rotation = UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix(&matrix));
+        let camera_to_world = Isometry3::from_parts(Translation3::from(translation), rotation);
+
+        views.push(DatasetView {
+            name: file_path.to_string(),
+            image_path,
+            camera: Camera {
+                view: camera_to_world.inverse(),
+                focal,
+                principal_point,
+                width,
+                height,
+            },
+        });
+    }
+    Ok(views)
+}
+
+#[cfg(test)]
+#[test]
+fn test_colmap_and_transforms_cameras_agree() {
+    let directory = std::env::temp_dir().join(format!("splat-cameras-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    // A camera at (0, 0, 5) looking at the origin, in both conventions.
+    std::fs::write(
+        directory.join("cameras.txt"),
+        "# comment\n1 PINHOLE 200 100 150 150 100 50\n",
+    )
+    .unwrap();
+    std::fs::write(
+        directory.join("images.txt"),
+        "# comment\n1 0 1 0 0 0 0 5 1 view.png\n\n",
+    )
+    .unwrap();
+    std::fs::write(
+        directory.join("transforms.json"),
+        r#"{"fl_x": 150, "fl_y": 150, "cx": 100, "cy": 50, "w": 200, "h": 100, "frames": [
+            {"file_path": "view", "transform_matrix": [[1,0,0,0],[0,1,0,0],[0,0,1,5],[0,0,0,1]]}]}"#,
+    )
+    .unwrap();
+    let colmap = load_dataset(&directory, None).unwrap();
+    let transforms = load_dataset(&directory.join("transforms.json"), None).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(colmap.len(), 1);
+    assert_eq!(transforms[0].image_path.extension().unwrap(), "png");
+    let point = nalgebra::Point3::new(0.3, -0.2, 1.0);
+    let a = colmap[0].camera.view * point;
+    let b = transforms[0].camera.view * point;
+    assert!((a - b).norm() < 1e-5);
+    assert!((colmap[0].camera.eye() - Vector3::new(0.0, 0.0, 5.0)).norm() < 1e-5);
+}
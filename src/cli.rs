@@ -1,21 +1,24 @@
 use crate::*;
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use humansize::{format_size, DECIMAL};
 use nalgebra::Vector3;
 use ply_rs as ply;
 use ply_rs::writer::Writer;
-use rand::prelude::*;
 use std::path::{Path, PathBuf};
 use strum::IntoEnumIterator;
 use tabled::builder::Builder;
-use tabled::{settings::Style, Table};
+use tabled::settings::Style;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+
+    /// Level for outputs saved as `.gz` (1 to 9) or `.zst` (1 to 22). Defaults to each codec's default level
+    #[arg(long, global = true, value_parser = clap::value_parser!(u32).range(1..=22))]
+    compression_level: Option<u32>,
 }
 
 #[derive(Subcommand)]
@@ -27,6 +30,22 @@ enum Commands {
     Info {
         #[arg(short, long)]
         input: PathBuf,
+
+        /// Print a text histogram of each attribute
+        #[arg(long)]
+        histograms: bool,
+
+        /// Number of histogram bins
+        #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u64).range(1..))]
+        bins: u64,
+
+        /// Output format. CSV prints one row of summary statistics per attribute
+        #[arg(long, value_enum, default_value = "table")]
+        output: OutputFormat,
+
+        /// Same as `--output json`, kept for scripts written before `--output`
+        #[arg(long, hide = true, conflicts_with = "output")]
+        json: bool,
     },
 
     /// Convert a gaussian splat file to another format
@@ -36,12 +55,194 @@ enum Commands {
 
         #[arg(short, long)]
         output: PathBuf,
+
+        /// Replace view dependent colors with those seen for `direction=x,y,z`, from `eye=x,y,z`, or averaged over
+        /// `hemisphere=x,y,z` or `sphere`. Otherwise formats with a single color keep only the DC term
+        #[arg(long)]
+        bake_color: Option<ColorBake>,
+
+        /// Codecs for `.splatx` outputs
+        #[arg(long, value_enum)]
+        preset: Option<SplatXPreset>,
+
+        /// Codec for one attribute of `.splatx` outputs, overriding the preset, e.g. `sh-rest=f16`. Attributes are
+        /// position, scale, rotation, opacity, color and sh-rest; codecs are f32, f16, fixed16 and u8
+        #[arg(long)]
+        codec: Vec<CodecOverride>,
+
+        /// Write a 2DGS `.ply` of surfels, flattening each splat along its smallest axis
+        #[arg(long)]
+        surfels: bool,
+    },
+
+    /// Merge multiple gaussian splat files, in any mix of formats, into a single scene
+    Merge {
+        #[arg(short, long, required = true)]
+        input: Vec<PathBuf>,
+
+        /// Transform for each input, in input order, e.g. `translate=1,0,0;rotate=0,90,0;scale=2` or `identity`
+        #[arg(short, long)]
+        transform: Vec<SceneTransform>,
+
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Spherical harmonic degree of the merged scene (defaults to the highest degree among the inputs)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=3))]
+        sh_degree: Option<u8>,
     },
 
     /// Guess the format of a gaussian splat file
     GuessFormat {
         #[arg(short, long)]
         input: PathBuf,
+
+        #[arg(long, value_enum, default_value = "table")]
+        output: OutputFormat,
+    },
+
+    /// Check a gaussian splat file for problems, exiting with an error if any are found
+    Validate {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        #[arg(long, value_enum, default_value = "table")]
+        output: OutputFormat,
+    },
+
+    /// Fix what `validate` finds where possible and drop splats that can not be fixed
+    Repair {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Smallest allowed linear scale
+        #[arg(long, default_value_t = 1e-7)]
+        min_scale: f32,
+
+        /// Largest allowed linear scale (defaults to the scene's bounding box diagonal, at least 1, or none for a scene with no extent)
+        #[arg(long)]
+        max_scale: Option<f32>,
+
+        /// Drop splats that exactly duplicate an earlier one
+        #[arg(long)]
+        drop_duplicates: bool,
+
+        /// Write the log of changes to a JSON file
+        #[arg(long)]
+        log: Option<PathBuf>,
+    },
+
+    /// Change the spherical harmonic degree of a gaussian splat file
+    ShDegree {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Degree to convert to. Higher bands are dropped, and missing bands are filled with zeros
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=3))]
+        to: u8,
+
+        /// Fit the lower degree to the displayed color over sampled view directions instead of truncating
+        #[arg(long)]
+        bake: bool,
+
+        /// Number of view directions sampled when baking
+        #[arg(long, default_value_t = 256, value_parser = clap::value_parser!(u64).range(16..))]
+        samples: u64,
+    },
+
+    /// Color grade a gaussian splat file. Exposure, white balance, saturation and hue work in linear light
+    Grade {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Exposure change in stops
+        #[arg(long, default_value_t = 0.0, allow_hyphen_values = true)]
+        exposure: f32,
+
+        /// Per channel gains as `r,g,b`
+        #[arg(long, default_value = "1,1,1", value_parser = parse_vector3)]
+        white_balance: Vector3<f32>,
+
+        /// Saturation, with 0 for grey and 1 for unchanged
+        #[arg(long, default_value_t = 1.0)]
+        saturation: f32,
+
+        /// Hue rotation in degrees
+        #[arg(long, default_value_t = 0.0, allow_hyphen_values = true)]
+        hue: f32,
+
+        /// Gamma applied to the displayed color; values above 1 brighten the mid tones
+        #[arg(long, default_value_t = 1.0)]
+        gamma: f32,
+    },
+
+    /// Remap opacities and scales: opacity gain and curve, scale clamps and the Mip-Splatting 3D filter
+    Adjust {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Factor applied to linear opacities
+        #[arg(long, default_value_t = 1.0)]
+        opacity_gain: f32,
+
+        /// Exponent applied to linear opacities before the gain; values below 1 boost faint splats
+        #[arg(long, default_value_t = 1.0)]
+        opacity_curve: f32,
+
+        /// Smallest allowed linear scale
+        #[arg(long)]
+        min_scale: Option<f32>,
+
+        /// Largest allowed linear scale
+        #[arg(long)]
+        max_scale: Option<f32>,
+
+        /// Convolve each splat with an isotropic gaussian of this standard deviation in world units, compensating
+        /// opacity, as the Mip-Splatting 3D filter does
+        #[arg(long)]
+        filter_3d: Option<f32>,
+    },
+
+    /// Compress spherical harmonics, and optionally scales and rotations, into k-means codebooks. Writes the
+    /// codebooks and indices to `.splatvq` outputs, and the quantised values to other formats
+    Quantise {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Codebook entries for the higher spherical harmonic bands
+        #[arg(long, default_value_t = 4096, value_parser = clap::value_parser!(u64).range(1..=65536))]
+        sh_codebook: u64,
+
+        /// Codebook entries for scales; scales are kept as floats without this
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..=65536))]
+        scale_codebook: Option<u64>,
+
+        /// Codebook entries for rotations; rotations are kept as floats without this
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..=65536))]
+        rotation_codebook: Option<u64>,
+
+        /// Rounds of k-means
+        #[arg(long, default_value_t = 10)]
+        iterations: usize,
+
+        /// Seed for reproducible codebooks
+        #[arg(long)]
+        seed: Option<u64>,
     },
 
     /// Reduce the number of splats in a gaussian splat file
@@ -63,6 +264,47 @@ enum Commands {
 
         #[arg(short, long)]
         output: PathBuf,
+
+        /// Seed for a reproducible shuffle
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+
+    /// Sort the splats in a gaussian splat file along a space filling curve or by an attribute
+    Sort {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        #[arg(short, long)]
+        output: PathBuf,
+
+        #[arg(long, value_enum)]
+        order: SortOrder,
+
+        /// Axis used by the `depth` order
+        #[arg(long, value_enum, default_value = "z")]
+        axis: Axis,
+
+        /// Reverse the sort direction
+        #[arg(long)]
+        reverse: bool,
+    },
+
+    /// Sort the splats back to front for one or more camera views
+    SortForView {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Camera position as `x,y,z`; repeat for several views to use the median order
+        #[arg(long, required = true, allow_hyphen_values = true, value_parser = parse_vector3)]
+        eye: Vec<Vector3<f32>>,
+
+        /// Point the camera looks at as `x,y,z`; give one per eye, or one shared by all eyes
+        #[arg(long, required = true, allow_hyphen_values = true, value_parser = parse_vector3)]
+        target: Vec<Vector3<f32>>,
     },
 
     Center {
@@ -73,10 +315,208 @@ enum Commands {
         output: PathBuf,
     },
 
+    /// Split a gaussian splat file into a grid of tile files with a JSON manifest
+    Tile {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Directory to write the tiles and `manifest.json` to
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Edge length of each (square) tile in scene units
+        #[arg(short, long)]
+        size: f32,
+
+        #[arg(long, value_enum, default_value = "xy")]
+        plane: TilePlane,
+
+        /// File extension (and therefore format) of the tile files
+        #[arg(long, default_value = "splat")]
+        extension: String,
+    },
+
+    /// Join the tiles listed in a tile manifest back into a single gaussian splat file
+    Join {
+        /// Path to a `manifest.json` written by `tile`
+        #[arg(short, long)]
+        input: PathBuf,
+
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    /// Render a gaussian splat file to a PNG image on the CPU
+    Render {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        #[arg(short, long)]
+        output: PathBuf,
+
+        #[arg(long, default_value_t = 800, value_parser = clap::value_parser!(u64).range(1..))]
+        width: u64,
+
+        #[arg(long, default_value_t = 600, value_parser = clap::value_parser!(u64).range(1..))]
+        height: u64,
+
+        #[command(flatten)]
+        camera: CameraArgs,
+
+        #[command(flatten)]
+        options: RenderOptions,
+    },
+
+    /// Render numbered PNG frames orbiting the scene or following a camera path
+    RenderOrbit {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Directory to write `frame_0000.png`, `frame_0001.png`, ... to
+        #[arg(short, long)]
+        output: PathBuf,
+
+        #[arg(long, default_value_t = 60)]
+        frames: usize,
+
+        #[arg(long, default_value_t = 800, value_parser = clap::value_parser!(u64).range(1..))]
+        width: u64,
+
+        #[arg(long, default_value_t = 600, value_parser = clap::value_parser!(u64).range(1..))]
+        height: u64,
+
+        /// Orbit center as `x,y,z` (defaults to the scene center)
+        #[arg(long, allow_hyphen_values = true, value_parser = parse_vector3)]
+        center: Option<Vector3<f32>>,
+
+        /// Orbit radius (defaults to a distance that frames the scene)
+        #[arg(long)]
+        radius: Option<f32>,
+
+        /// Orbit elevation in degrees above the plane perpendicular to `up`
+        #[arg(long, default_value_t = 20.0, allow_hyphen_values = true)]
+        elevation: f32,
+
+        /// JSON camera path (`{"keyframes": [{"time", "eye", "target", "fov"?}, ...]}`) to follow instead of orbiting
+        #[arg(long)]
+        path: Option<PathBuf>,
+
+        /// Camera up vector as `x,y,z`
+        #[arg(long, default_value = "0,1,0", allow_hyphen_values = true, value_parser = parse_vector3)]
+        up: Vector3<f32>,
+
+        /// Vertical field of view in degrees
+        #[arg(long, default_value_t = 50.0)]
+        fov: f32,
+
+        #[command(flatten)]
+        options: RenderOptions,
+    },
+
+    /// Write a top-down orthographic PNG of splat count, summed opacity or mean color per grid cell
+    DensityMap {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Axis to project along
+        #[arg(long, value_enum, default_value = "y")]
+        axis: Axis,
+
+        /// Number of cells along the longer side of the image
+        #[arg(long, default_value_t = 1024)]
+        resolution: usize,
+
+        #[arg(long, value_enum, default_value = "count")]
+        mode: DensityMode,
+
+        /// Map counts and opacities to the heatmap linearly instead of logarithmically
+        #[arg(long)]
+        linear: bool,
+    },
+
+    /// Render from every camera of a COLMAP or nerfstudio dataset and compare against its images
+    Evaluate {
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// COLMAP text model directory (`cameras.txt`, `images.txt`) or nerfstudio `transforms.json`
+        #[arg(short, long)]
+        cameras: PathBuf,
+
+        /// Directory of ground truth images (defaults to the dataset's own image directory)
+        #[arg(long)]
+        images: Option<PathBuf>,
+
+        /// Background color as `r,g,b` in [0, 1], also used behind transparent ground truth images
+        #[arg(long, default_value = "0,0,0", value_parser = parse_vector3)]
+        background: Vector3<f32>,
+
+        /// Only evaluate every nth view
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+        every: u64,
+
+        /// Directory to write the rendered views to
+        #[arg(long)]
+        renders: Option<PathBuf>,
+
+        /// Write per-view and mean metrics to a JSON file
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+
+    /// Compare two gaussian splat files attribute by attribute and optionally render the differences
+    Diff {
+        #[arg(short, long)]
+        a: PathBuf,
+
+        #[arg(short, long)]
+        b: PathBuf,
+
+        /// How splats in `a` are paired with splats in `b`
+        #[arg(long, value_enum, default_value = "index")]
+        matching: DiffMatching,
+
+        /// Directory to write renders of both scenes and a difference heatmap for each view to
+        #[arg(long)]
+        renders: Option<PathBuf>,
+
+        /// Number of views, evenly spaced on an orbit around the scene
+        #[arg(long, default_value_t = 4)]
+        views: usize,
+
+        #[arg(long, default_value_t = 400, value_parser = clap::value_parser!(u64).range(1..))]
+        width: u64,
+
+        #[arg(long, default_value_t = 300, value_parser = clap::value_parser!(u64).range(1..))]
+        height: u64,
+
+        /// Color difference shown as white in heatmaps (defaults to the largest difference)
+        #[arg(long)]
+        heatmap_scale: Option<f32>,
+
+        /// Write the comparison to a JSON file
+        #[arg(long)]
+        report: Option<PathBuf>,
+    },
+
     /// Dump the splats in a gaussian splat file
     Dump {
         #[arg(short, long)]
         input: PathBuf,
+
+        #[arg(long, value_enum, default_value = "table")]
+        output: OutputFormat,
+
+        /// Only dump splats in `start..end`; either end may be left out
+        #[arg(long)]
+        range: Option<IndexRange>,
+
+        /// Columns to dump, defaulting to all of them
+        #[arg(long, value_enum, value_delimiter = ',')]
+        columns: Vec<DumpColumn>,
     },
 
     /// Convert a ply file to ascii
@@ -95,24 +535,213 @@ enum Commands {
     },
 }
 
+#[derive(Args)]
+struct CameraArgs {
+    /// Camera position as `x,y,z` (defaults to a point in front of the scene center)
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_vector3)]
+    eye: Option<Vector3<f32>>,
+
+    /// Point the camera looks at as `x,y,z` (defaults to the scene center)
+    #[arg(long, allow_hyphen_values = true, value_parser = parse_vector3)]
+    target: Option<Vector3<f32>>,
+
+    /// Camera up vector as `x,y,z`
+    #[arg(long, default_value = "0,1,0", allow_hyphen_values = true, value_parser = parse_vector3)]
+    up: Vector3<f32>,
+
+    /// Vertical field of view in degrees
+    #[arg(long, default_value_t = 50.0)]
+    fov: f32,
+}
+
+#[derive(Args)]
+struct RenderOptions {
+    /// Background color as `r,g,b` in [0, 1]
+    #[arg(long, default_value = "0,0,0", value_parser = parse_vector3)]
+    background: Vector3<f32>,
+
+    /// Write RGBA color images with a transparent background instead
+    #[arg(long)]
+    transparent: bool,
+
+    /// Passes to write. Passes other than color are written next to the output as `<name>.<pass>.png`
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "color")]
+    passes: Vec<RenderPass>,
+
+    /// Write depth, alpha and normal passes as float PFM files instead of 16 bit PNGs
+    #[arg(long)]
+    float: bool,
+
+    /// Multiplier from depth to 16 bit PNG values (defaults to fitting the farthest depth to 65535)
+    #[arg(long)]
+    depth_scale: Option<f32>,
+}
+
+impl CameraArgs {
+    fn camera(&self, splats: &[UberSplat], width: usize, height: usize) -> Camera {
+        let target = self.target.unwrap_or_else(|| find_center(splats));
+        let eye = self
+            .eye
+            .unwrap_or_else(|| target + Vector3::new(0.0, 0.0, -framing_distance(splats, &target)));
+        Camera::look_at(&eye, &target, &self.up, self.fov, width, height)
+    }
+}
+
 impl Cli {
     pub fn main() -> Result<()> {
         let args = Cli::parse();
+        if let Some(level) = args.compression_level {
+            set_compression_level(level);
+        }
+        // Check the level before any input is loaded, rather than when the output is created.
+        let output = match &args.command {
+            Some(
+                Commands::Convert { output, .. }
+                | Commands::Merge { output, .. }
+                | Commands::Repair { output, .. }
+                | Commands::ShDegree { output, .. }
+                | Commands::Grade { output, .. }
+                | Commands::Adjust { output, .. }
+                | Commands::Quantise { output, .. }
+                | Commands::Reduce { output, .. }
+                | Commands::Shuffle { output, .. }
+                | Commands::Sort { output, .. }
+                | Commands::SortForView { output, .. }
+                | Commands::Center { output, .. }
+                | Commands::Join { output, .. },
+            ) => Some(output.clone()),
+            Some(Commands::Tile {
+                output, extension, ..
+            }) => Some(output.join(format!("tile.{}", extension))),
+            _ => None,
+        };
+        if let Some(output) = output {
+            check_compression_level(&output)?;
+        }
         match args.command {
             Some(Commands::Formats {}) => {
                 println!("Supported formats:");
                 for format in SplatFormats::iter() {
-                    println!("{:?}: {}", format, format.description());
+                    println!(
+                        "{:?}: {} Color space: {}.",
+                        format,
+                        format.description(),
+                        format.color_space()
+                    );
                 }
             }
-            Some(Commands::Info { input }) => {
-                Cli::info(input)?;
+            Some(Commands::Info {
+                input,
+                histograms,
+                bins,
+                output,
+                json,
+            }) => {
+                let output = if json { OutputFormat::Json } else { output };
+                Cli::info(input, histograms, bins as usize, output)?;
+            }
+            Some(Commands::Convert {
+                input,
+                output,
+                bake_color,
+                preset,
+                codec,
+                surfels,
+            }) => {
+                Cli::convert(input, output, bake_color, preset, codec, surfels)?;
+            }
+            Some(Commands::Merge {
+                input,
+                transform,
+                output,
+                sh_degree,
+            }) => {
+                Cli::merge(input, transform, output, sh_degree.map(usize::from))?;
+            }
+            Some(Commands::GuessFormat { input, output }) => {
+                Cli::guess_format(&input, output)?;
+            }
+            Some(Commands::Validate { input, output }) => {
+                Cli::validate(&input, output)?;
+            }
+            Some(Commands::Repair {
+                input,
+                output,
+                min_scale,
+                max_scale,
+                drop_duplicates,
+                log,
+            }) => {
+                let options = RepairOptions {
+                    min_scale,
+                    max_scale,
+                    drop_duplicates,
+                };
+                Cli::repair(&input, &output, &options, log.as_deref())?;
+            }
+            Some(Commands::ShDegree {
+                input,
+                output,
+                to,
+                bake,
+                samples,
+            }) => {
+                let baker = bake.then(|| ShBaker::new(to as usize, samples as usize));
+                Cli::sh_degree(&input, &output, to as usize, baker.as_ref())?;
+            }
+            Some(Commands::Grade {
+                input,
+                output,
+                exposure,
+                white_balance,
+                saturation,
+                hue,
+                gamma,
+            }) => {
+                let grade = Grade {
+                    exposure,
+                    white_balance,
+                    saturation,
+                    hue,
+                    gamma,
+                };
+                Cli::grade(&input, &output, &grade)?;
             }
-            Some(Commands::Convert { input, output }) => {
-                Cli::convert(input, output)?;
+            Some(Commands::Adjust {
+                input,
+                output,
+                opacity_gain,
+                opacity_curve,
+                min_scale,
+                max_scale,
+                filter_3d,
+            }) => {
+                let options = AdjustOptions {
+                    opacity_gain,
+                    opacity_curve,
+                    min_scale,
+                    max_scale,
+                    filter_3d,
+                };
+                Cli::adjust(&input, &output, &options)?;
             }
-            Some(Commands::GuessFormat { input }) => {
-                Cli::guess_format(&input)?;
+            Some(Commands::Quantise {
+                input,
+                output,
+                sh_codebook,
+                scale_codebook,
+                rotation_codebook,
+                iterations,
+                seed,
+            }) => {
+                let options = VqOptions {
+                    sh_codebook: sh_codebook as usize,
+                    scale_codebook: scale_codebook.map(|size| size as usize),
+                    rotation_codebook: rotation_codebook.map(|size| size as usize),
+                    iterations,
+                    seed,
+                };
+                Cli::quantise(&input, &output, &options)?;
             }
             Some(Commands::Reduce {
                 input,
@@ -121,17 +750,205 @@ impl Cli {
             }) => {
                 Cli::reduce(input, output, limit).unwrap();
             }
-            Some(Commands::Shuffle { input, output }) => {
-                Cli::shuffle(input, output).unwrap();
+            Some(Commands::Shuffle {
+                input,
+                output,
+                seed,
+            }) => {
+                Cli::shuffle(input, output, seed).unwrap();
+            }
+            Some(Commands::Sort {
+                input,
+                output,
+                order,
+                axis,
+                reverse,
+            }) => {
+                modify_splats(&input, &output, |splats| {
+                    sort_splats(splats, order, axis, reverse);
+                })?;
+            }
+            Some(Commands::SortForView {
+                input,
+                output,
+                eye,
+                target,
+            }) => {
+                Cli::sort_for_view(input, output, eye, target)?;
             }
             Some(Commands::Center { input, output }) => {
                 modify_splats(&input, &output, |splats| {
                     center_splats(splats);
                 })?;
             }
-
-            Some(Commands::Dump { input }) => {
-                Cli::dump(input)?;
+            Some(Commands::Tile {
+                input,
+                output,
+                size,
+                plane,
+                extension,
+            }) => {
+                let manifest = save_tiles(&input, &output, plane, size, &extension)?;
+                println!(
+                    "Wrote {} splats to {} tiles",
+                    manifest.count,
+                    manifest.tiles.len()
+                );
+            }
+            Some(Commands::Join { input, output }) => {
+                let splats = load_tiles(&input)?;
+                save_output(splats, &output)?;
+            }
+            Some(Commands::Render {
+                input,
+                output,
+                width,
+                height,
+                camera,
+                options,
+            }) => {
+                let splats = load_splats(&input)?;
+                let camera = camera.camera(&splats, width as usize, height as usize);
+                Cli::render(&splats, &camera, &output, &options)?;
+            }
+            Some(Commands::RenderOrbit {
+                input,
+                output,
+                frames,
+                width,
+                height,
+                center,
+                radius,
+                elevation,
+                path,
+                up,
+                fov,
+                options,
+            }) => {
+                let splats = load_splats(&input)?;
+                let poses = match path {
+                    Some(path) => CameraPath::load(&path)?.poses(frames, fov),
+                    None => {
+                        let center = center.unwrap_or_else(|| find_center(&splats));
+                        let radius = radius.unwrap_or_else(|| framing_distance(&splats, &center));
+                        orbit_poses(&center, &up, radius, elevation, frames, fov)
+                    }
+                };
+                std::fs::create_dir_all(&output)?;
+                for (frame, pose) in poses.iter().enumerate() {
+                    let camera = Camera::look_at(
+                        &pose.eye,
+                        &pose.target,
+                        &up,
+                        pose.fov,
+                        width as usize,
+                        height as usize,
+                    );
+                    let path = output.join(format!("frame_{:04}.png", frame));
+                    Cli::render(&splats, &camera, &path, &options)?;
+                }
+                println!("Wrote {} frames to {}", poses.len(), output.display());
+            }
+            Some(Commands::DensityMap {
+                input,
+                output,
+                axis,
+                resolution,
+                mode,
+                linear,
+            }) => {
+                if resolution == 0 {
+                    anyhow::bail!("Resolution must be at least 1");
+                }
+                let splats = load_splats(&input)?;
+                let map = density_map(&splats, axis, resolution);
+                let (color, alpha) = map.to_image(mode, linear);
+                save_png_rgb8(&output, map.width, map.height, &color, Some(&alpha))?;
+                let (column_axis, row_axis) = plane_axes(axis);
+                let names = ["x", "y", "z"];
+                println!(
+                    "{}x{} cells of size {}, columns along {} from {}, rows along {} from {}",
+                    map.width,
+                    map.height,
+                    map.cell_size,
+                    names[column_axis],
+                    map.origin.x,
+                    names[row_axis],
+                    map.origin.y
+                );
+                println!(
+                    "Max splats per cell: {}",
+                    map.count.iter().max().unwrap_or(&0)
+                );
+            }
+            Some(Commands::Evaluate {
+                input,
+                cameras,
+                images,
+                background,
+                every,
+                renders,
+                report,
+            }) => {
+                let views = load_dataset(&cameras, images.as_deref())?
+                    .into_iter()
+                    .step_by(every as usize)
+                    .collect::<Vec<_>>();
+                Cli::evaluate(
+                    &input,
+                    &views,
+                    &background,
+                    renders.as_deref(),
+                    report.as_deref(),
+                )?;
+            }
+            Some(Commands::Diff {
+                a,
+                b,
+                matching,
+                renders,
+                views,
+                width,
+                height,
+                heatmap_scale,
+                report,
+            }) => {
+                let a = load_splats(&a)?;
+                let b = load_splats(&b)?;
+                let diff = diff_scenes(&a, &b, matching);
+                Cli::print_diff(&diff);
+                let mut views_psnr = Vec::new();
+                if let Some(renders) = renders {
+                    let center = find_center(&a);
+                    let radius = framing_distance(&a, &center);
+                    let poses = orbit_poses(&center, &Vector3::y(), radius, 20.0, views, 50.0);
+                    let cameras = poses
+                        .iter()
+                        .map(|p| {
+                            Camera::look_at(
+                                &p.eye,
+                                &p.target,
+                                &Vector3::y(),
+                                p.fov,
+                                width as usize,
+                                height as usize,
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    views_psnr = Cli::render_diff(&a, &b, &cameras, &renders, heatmap_scale)?;
+                }
+                if let Some(report) = report {
+                    let json = serde_json::json!({ "diff": diff, "views_psnr": views_psnr });
+                    std::fs::write(report, serde_json::to_string_pretty(&json)?)?;
+                }
+            }
+            Some(Commands::Dump {
+                input,
+                output,
+                range,
+                columns,
+            }) => {
+                Cli::dump(input, output, range, columns)?;
             }
             Some(Commands::PlyToAscii { input, output }) => {
                 Cli::ply_to_ascii(input, output)?;
@@ -146,69 +963,339 @@ impl Cli {
         Ok(())
     }
 
-    fn guess_format(input: &Path) -> Result<()> {
-        let ordered_results = vec![
+    fn guess_format(input: &Path, output: OutputFormat) -> Result<()> {
+        let ordered_results = [
             (SplatFormats::SplatA, SplatA::is_format(input)),
             (SplatFormats::SplatB, SplatB::is_format(input)),
             (SplatFormats::SplatC, SplatC::is_format(input)),
+            (SplatFormats::SplatVq, SplatVq::is_format(input)),
+            (SplatFormats::SplatX, SplatX::is_format(input)),
+            (SplatFormats::Splat2d, Splat2d::is_format(input)),
         ];
-        for result in &ordered_results {
-            println!("{:?}", result);
+        let guess = guess_format(input).map(|format| format!("{:?}", format));
+        let columns = ["format", "result", "confidence", "reason", "guessed"].map(String::from);
+        let rows = ordered_results.iter().map(|(format, result)| {
+            let format = format!("{:?}", format);
+            let (name, confidence, reason) = match result {
+                FormatResult::Yes => ("yes", Some(1.0), None),
+                FormatResult::Maybe(confidence) => ("maybe", *confidence, None),
+                FormatResult::No(reason) => ("no", None, Some(reason.clone())),
+            };
+            vec![
+                serde_json::Value::from(format.clone()),
+                name.into(),
+                confidence.map_or(serde_json::Value::Null, f32_value),
+                reason.into(),
+                (guess.as_ref() == Some(&format)).into(),
+            ]
+        });
+        write_records(&mut std::io::stdout().lock(), output, &columns, rows)
+    }
+
+    fn validate(input: &Path, output: OutputFormat) -> Result<()> {
+        let validation = validate_file(input);
+        let has_errors = validation.has_errors();
+        let issues = validation.into_issues();
+        if output == OutputFormat::Table && issues.is_empty() {
+            println!("No issues found");
+            return Ok(());
+        }
+        let columns = ["severity", "check", "count", "indices", "message"].map(String::from);
+        let rows = issues.iter().map(|issue| {
+            let indices = issue
+                .indices
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>();
+            vec![
+                serde_json::to_value(issue.severity).unwrap(),
+                issue.check.clone().into(),
+                issue.count.into(),
+                indices.join(" ").into(),
+                issue.message.clone().into(),
+            ]
+        });
+        write_records(&mut std::io::stdout().lock(), output, &columns, rows)?;
+        if has_errors {
+            anyhow::bail!("{} failed validation", input.display());
         }
-        let format = guess_format(input).unwrap();
-        println!("{:?}", format);
         Ok(())
     }
 
-    fn info(input: PathBuf) -> Result<()> {
-        let format = guess_format(&input).unwrap();
+    fn repair(
+        input: &Path,
+        output: &Path,
+        options: &RepairOptions,
+        log_path: Option<&Path>,
+    ) -> Result<()> {
+        // Written so NaN fails each check.
+        if !(options.min_scale > 0.0 && options.min_scale.is_finite())
+            || options
+                .max_scale
+                .is_some_and(|max| !(max >= options.min_scale && max.is_finite()))
+        {
+            anyhow::bail!("Scale limits must be positive with the maximum above the minimum");
+        }
+        let splats = load_splats(input)?;
+        let count = splats.len();
+        let (splats, log) = repair_splats(splats, options);
+        println!("Kept {} of {} splats", splats.len(), count);
+        if log.changes.is_empty() {
+            println!("Nothing to repair");
+        } else {
+            let columns = ["change", "count", "indices"].map(String::from);
+            let rows = log.changes.iter().map(|change| {
+                let indices = change
+                    .indices
+                    .iter()
+                    .map(|i| i.to_string())
+                    .collect::<Vec<_>>();
+                vec![
+                    change.category.clone().into(),
+                    change.count.into(),
+                    indices.join(" ").into(),
+                ]
+            });
+            write_records(
+                &mut std::io::stdout().lock(),
+                OutputFormat::Table,
+                &columns,
+                rows,
+            )?;
+        }
+        if let Some(log_path) = log_path {
+            std::fs::write(log_path, serde_json::to_string_pretty(&log)?)?;
+        }
+        save_output(splats, output)
+    }
+
+    fn info(input: PathBuf, histograms: bool, bins: usize, output: OutputFormat) -> Result<()> {
+        let Some(format) = guess_format(&input) else {
+            anyhow::bail!("Unknown format for {}", input.display());
+        };
+        let size = std::fs::metadata(&input)?.len();
+        let splats = load_splats(&input)?;
+        let statistics = scene_statistics(&splats, bins);
+        let report = serde_json::json!({
+            "format": format!("{:?}", format),
+            "description": format.description(),
+            "color_space": format.color_space().to_string(),
+            "size": size,
+            "statistics": statistics,
+        });
+        match output {
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                return Ok(());
+            }
+            OutputFormat::Ndjson => {
+                println!("{}", report);
+                return Ok(());
+            }
+            OutputFormat::Csv => {
+                let mut columns = vec!["attribute".to_string(), "count".to_string()];
+                columns.push("min".to_string());
+                columns.extend(PERCENTILES.iter().map(|p| format!("p{}", p)));
+                columns.extend(["max".to_string(), "mean".to_string()]);
+                let rows = statistics.distributions.iter().map(|d| {
+                    let mut row = vec![serde_json::Value::from(d.name.clone()), d.count.into()];
+                    row.extend(
+                        [d.min]
+                            .iter()
+                            .chain(&d.percentiles)
+                            .chain([&d.max, &d.mean])
+                            .map(|v| f32_value(*v)),
+                    );
+                    row
+                });
+                return write_records(&mut std::io::stdout().lock(), output, &columns, rows);
+            }
+            OutputFormat::Table => (),
+        }
+
         println!("Format: {:?} / {}", format, format.description());
-        // print file size
-        let metadata = std::fs::metadata(&input)?;
-        let size = metadata.len();
+        println!("Color space: {}", format.color_space());
         println!("Size: {}", format_size(size, DECIMAL));
-        let splats = load_splats(&input)?;
         println!("# Splats: {}", splats.len());
+        println!("Min position: {:?}", statistics.min_position);
+        println!("Max position: {:?}", statistics.max_position);
+        println!("Avg position: {:?}", statistics.center);
 
-        let positions = splats
-            .iter()
-            .map(|splat| splat.position)
-            .collect::<Vec<Vector3<f32>>>();
-        let mut min_position = positions[0];
-        let mut max_position = positions[0];
-        let mut sum_position = Vector3::new(0.0, 0.0, 0.0);
-
-        for position in positions {
-            min_position = Vector3::new(
-                min_position.x.min(position.x),
-                min_position.y.min(position.y),
-                min_position.z.min(position.z),
-            );
-            max_position = Vector3::new(
-                max_position.x.max(position.x),
-                max_position.y.max(position.y),
-                max_position.z.max(position.z),
+        let mut builder = Builder::default();
+        let mut header = vec!["Attribute".to_string(), "Min".to_string()];
+        header.extend(PERCENTILES.iter().map(|p| format!("P{}", p)));
+        header.extend(["Max".to_string(), "Mean".to_string()]);
+        builder.push_record(header);
+        for distribution in &statistics.distributions {
+            let mut record = vec![distribution.name.clone()];
+            record.extend(
+                [distribution.min]
+                    .iter()
+                    .chain(&distribution.percentiles)
+                    .chain([&distribution.max, &distribution.mean])
+                    .map(|v| format!("{:.4}", v)),
             );
-            sum_position += position;
+            builder.push_record(record);
         }
+        println!("{}", builder.build().with(Style::modern()));
 
-        let avg_position = sum_position / splats.len() as f32;
-        println!("Min position: {:?}", min_position);
-        println!("Max position: {:?}", max_position);
-        println!("Avg position: {:?}", avg_position);
+        let degenerate = &statistics.degenerate;
+        println!("Degenerate splats:");
+        println!("  Non-finite values: {}", degenerate.non_finite);
+        println!("  Zero rotation: {}", degenerate.zero_rotation);
+        println!("  Zero scale: {}", degenerate.zero_scale);
+        println!(
+            "  Transparent (opacity < 1/255): {}",
+            degenerate.transparent
+        );
+        println!("  Needles (anisotropy > 100): {}", degenerate.needle);
 
+        if histograms {
+            for distribution in &statistics.distributions {
+                println!("\n{}", distribution.name);
+                print!("{}", distribution.text_histogram(40));
+            }
+        }
         Ok(())
     }
 
-    fn convert(input: PathBuf, output: PathBuf) -> Result<()> {
-        let splats = load_splats(&input)?;
+    fn convert(
+        input: PathBuf,
+        output: PathBuf,
+        bake_color: Option<ColorBake>,
+        preset: Option<SplatXPreset>,
+        codec_overrides: Vec<CodecOverride>,
+        surfels: bool,
+    ) -> Result<()> {
+        let mut splats = load_splats(&input)?;
+        if let Some(bake_color) = bake_color {
+            let baked = bake_colors(&mut splats, &bake_color);
+            println!("Baked the view dependent color of {} splats", baked);
+        }
+        if surfels {
+            if inner_path(&output).extension() != Some("ply".as_ref()) {
+                anyhow::bail!("--surfels only applies to .ply outputs");
+            }
+            return Splat2d::save(&splats, &output);
+        }
+        if preset.is_none() && codec_overrides.is_empty() {
+            return save_output(splats, &output);
+        }
+        if !matches!(guess_format(&output), Some(SplatFormats::SplatX)) {
+            anyhow::bail!("--preset and --codec only apply to .splatx outputs");
+        }
+        let mut codecs = preset.unwrap_or(SplatXPreset::Web).codecs();
+        for codec_override in codec_overrides {
+            codecs.set(codec_override.attribute, codec_override.codec);
+        }
+        SplatX::save_with_codecs(&splats, &output, &codecs)
+    }
 
-        if output.extension() == Some("json".as_ref()) {
-            save_to_json(splats, &output)?;
-        } else {
-            let output_format = guess_format(&output).unwrap();
-            save_splats(splats, output_format, &output)?;
+    fn merge(
+        inputs: Vec<PathBuf>,
+        transforms: Vec<SceneTransform>,
+        output: PathBuf,
+        sh_degree: Option<usize>,
+    ) -> Result<()> {
+        if !transforms.is_empty() && transforms.len() != inputs.len() {
+            anyhow::bail!(
+                "Got {} transforms for {} inputs; give one per input or none",
+                transforms.len(),
+                inputs.len()
+            );
+        }
+        let mut scenes = Vec::new();
+        for (index, input) in inputs.iter().enumerate() {
+            let splats = load_splats(input)?;
+            let transform = transforms.get(index).cloned().unwrap_or_default();
+            scenes.push((splats, transform));
+        }
+        let splats = merge_scenes(scenes, sh_degree);
+        println!("Merged {} files into {} splats", inputs.len(), splats.len());
+        save_output(splats, &output)
+    }
+
+    fn grade(input: &Path, output: &Path, grade: &Grade) -> Result<()> {
+        if grade.gamma <= 0.0 || grade.saturation < 0.0 || grade.white_balance.min() <= 0.0 {
+            anyhow::bail!(
+                "Gamma and white balance gains must be positive and saturation not negative"
+            );
+        }
+        modify_splats(input, output, |splats| {
+            for splat in splats.iter_mut() {
+                splat.color = grade.grade_color(&splat.color);
+            }
+            println!("Graded {} splats", splats.len());
+        })
+    }
+
+    fn adjust(input: &Path, output: &Path, options: &AdjustOptions) -> Result<()> {
+        // Written so NaN fails each check.
+        let non_negative = |value: f32| value >= 0.0 && value.is_finite();
+        let positive = |value: f32| value > 0.0 && value.is_finite();
+        if !non_negative(options.opacity_gain) || !positive(options.opacity_curve) {
+            anyhow::bail!(
+                "Opacity gain must not be negative and the opacity curve must be positive"
+            );
+        }
+        if options.filter_3d.is_some_and(|size| !positive(size)) {
+            anyhow::bail!("The 3D filter size must be positive");
+        }
+        if options.min_scale.is_some_and(|min| !non_negative(min))
+            || options.max_scale.is_some_and(|max| !positive(max))
+            || options
+                .min_scale
+                .zip(options.max_scale)
+                .is_some_and(|(min, max)| max < min)
+        {
+            anyhow::bail!("Scale limits must not be negative with the maximum above the minimum");
+        }
+        modify_splats(input, output, |splats| {
+            let changed = adjust_splats(splats, options);
+            println!("Adjusted {} of {} splats", changed, splats.len());
+        })
+    }
+
+    fn sh_degree(
+        input: &Path,
+        output: &Path,
+        degree: usize,
+        baker: Option<&ShBaker>,
+    ) -> Result<()> {
+        let mut splats = load_splats(input)?;
+        for splat in &mut splats {
+            // Splats without spherical harmonics are converted too, so the output always has degree `degree`.
+            splat.color = match (&splat.color, baker) {
+                (Color::SphericalHarmonic(dc, rest), Some(baker)) => {
+                    let (dc, rest) = baker.bake(dc, rest);
+                    Color::SphericalHarmonic(dc, rest).to_spherical_harmonic(degree)
+                }
+                (color, _) => color.to_spherical_harmonic(degree),
+            };
         }
+        println!("Converted {} splats to degree {}", splats.len(), degree);
+        save_output(splats, output)
+    }
+
+    fn quantise(input: &Path, output: &Path, options: &VqOptions) -> Result<()> {
+        let splats = load_splats(input)?;
+        let quantised = if matches!(guess_format(output), Some(SplatFormats::SplatVq)) {
+            let scene = SplatVq::save_with_options(&splats, output, options)?;
+            scene.decode()
+        } else {
+            let quantised = VqScene::encode(&splats, options)?.decode();
+            save_output(quantised.clone(), output)?;
+            quantised
+        };
+        let size = |path: &Path| std::fs::metadata(path).map_or(0, |m| m.len());
+        let (input_size, output_size) = (size(input), size(output));
+        println!(
+            "Size: {} -> {} ({:.1}%)",
+            format_size(input_size, DECIMAL),
+            format_size(output_size, DECIMAL),
+            100.0 * output_size as f64 / input_size.max(1) as f64
+        );
+        Cli::print_diff(&diff_scenes(&splats, &quantised, DiffMatching::Index));
         Ok(())
     }
 
@@ -220,34 +1307,290 @@ impl Cli {
         Ok(())
     }
 
-    fn shuffle(input: PathBuf, output: PathBuf) -> Result<()> {
+    fn shuffle(input: PathBuf, output: PathBuf, seed: Option<u64>) -> Result<()> {
+        let mut rng = splat_rng(seed);
         modify_splats(&input, &output, |splats| {
-            let mut rng = thread_rng();
-            splats.shuffle(&mut rng);
+            shuffle_splats(splats, &mut rng);
         })
     }
 
-    fn dump(input: PathBuf) -> Result<()> {
-        let splats = load_splats(&input)?;
-        let table = Table::new(splats).with(Style::modern()).to_string();
-        println!("{}", table);
+    fn sort_for_view(
+        input: PathBuf,
+        output: PathBuf,
+        eyes: Vec<Vector3<f32>>,
+        targets: Vec<Vector3<f32>>,
+    ) -> Result<()> {
+        let views: Vec<(Vector3<f32>, Vector3<f32>)> = match targets.as_slice() {
+            [target] => eyes.iter().map(|eye| (*eye, *target)).collect(),
+            _ if targets.len() == eyes.len() => eyes.into_iter().zip(targets).collect(),
+            _ => anyhow::bail!("Give one target per eye, or a single target for all eyes"),
+        };
+        let mut splats = load_splats(&input)?;
+        sort_for_views(&mut splats, &views)?;
+        save_output(splats, &output)
+    }
+
+    fn render(
+        splats: &[UberSplat],
+        camera: &Camera,
+        output: &Path,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        if options.passes.contains(&RenderPass::Color) {
+            Cli::render_color(splats, camera, output, options)?;
+        }
+        let geometry_passes = options
+            .passes
+            .iter()
+            .filter(|pass| **pass != RenderPass::Color)
+            .collect::<Vec<_>>();
+        if geometry_passes.is_empty() {
+            return Ok(());
+        }
+        let geometry = render_geometry(splats, camera);
+        let (width, height) = (geometry.width, geometry.height);
+        for pass in geometry_passes {
+            let name = format!("{:?}", pass).to_lowercase();
+            let extension = if options.float { "pfm" } else { "png" };
+            let path = output.with_extension(format!("{}.{}", name, extension));
+            let (channels, mut values) = match pass {
+                RenderPass::Depth => (1, geometry.depth.clone()),
+                RenderPass::Alpha => (1, geometry.alpha.clone()),
+                RenderPass::Normal => (
+                    3,
+                    geometry
+                        .normal
+                        .iter()
+                        .flat_map(|n| n.iter().copied())
+                        .collect(),
+                ),
+                RenderPass::Color => unreachable!(),
+            };
+            if options.float {
+                save_pfm(&path, width, height, channels, &values)?;
+                continue;
+            }
+            match pass {
+                RenderPass::Depth => {
+                    let max_depth = values.iter().copied().fold(0.0, f32::max);
+                    let scale = options.depth_scale.unwrap_or(if max_depth > 0.0 {
+                        65535.0 / max_depth
+                    } else {
+                        1.0
+                    });
+                    println!("{}: 16 bit value = depth * {}", path.display(), scale);
+                    values.iter_mut().for_each(|v| *v = *v * scale / 65535.0);
+                }
+                // Map unit normals from [-1, 1] to [0, 1], leaving empty pixels black.
+                RenderPass::Normal => {
+                    for normal in values.chunks_mut(3) {
+                        if normal.iter().any(|v| *v != 0.0) {
+                            normal.iter_mut().for_each(|v| *v = *v * 0.5 + 0.5);
+                        }
+                    }
+                }
+                _ => (),
+            }
+            save_png16(&path, width, height, channels, &values)?;
+        }
+        Ok(())
+    }
+
+    fn render_color(
+        splats: &[UberSplat],
+        camera: &Camera,
+        output: &Path,
+        options: &RenderOptions,
+    ) -> Result<()> {
+        if !options.transparent {
+            let image = render_splats(splats, camera, &options.background);
+            return save_png_rgb8(output, image.width, image.height, &image.color, None);
+        }
+        // PNG alpha is not premultiplied, so undo the compositing against black.
+        let image = render_splats(splats, camera, &Vector3::zeros());
+        let color = image
+            .color
+            .iter()
+            .zip(&image.alpha)
+            .map(|(c, a)| if *a > 0.0 { c / *a } else { *c })
+            .collect::<Vec<_>>();
+        save_png_rgb8(
+            output,
+            image.width,
+            image.height,
+            &color,
+            Some(&image.alpha),
+        )
+    }
+
+    fn evaluate(
+        input: &Path,
+        views: &[DatasetView],
+        background: &Vector3<f32>,
+        renders: Option<&Path>,
+        report: Option<&Path>,
+    ) -> Result<()> {
+        if views.is_empty() {
+            anyhow::bail!("The dataset has no views");
+        }
+        let splats = load_splats(input)?;
+        if let Some(renders) = renders {
+            std::fs::create_dir_all(renders)?;
+        }
+        let mut results = Vec::new();
+        for view in views {
+            let truth = load_image(&view.image_path, background)?;
+            let camera = view.camera_for_size(truth.width, truth.height);
+            let image = render_splats(&splats, &camera, background);
+            if let Some(renders) = renders {
+                let name = Path::new(&view.name).with_extension("png");
+                let path = renders.join(name.file_name().unwrap());
+                save_png_rgb8(&path, image.width, image.height, &image.color, None)?;
+            }
+            results.push(ViewMetrics {
+                name: view.name.clone(),
+                psnr: psnr(&image.color, &truth.color),
+                ssim: ssim(&image.color, &truth.color, truth.width, truth.height),
+                mae: mean_absolute_error(&image.color, &truth.color),
+            });
+        }
+        let mean = ViewMetrics::mean(&results);
+
+        let mut builder = Builder::default();
+        builder.push_record(["View", "PSNR", "SSIM", "MAE"]);
+        for metrics in results.iter().chain([&mean]) {
+            builder.push_record([
+                metrics.name.clone(),
+                format!("{:.3}", metrics.psnr),
+                format!("{:.4}", metrics.ssim),
+                format!("{:.4}", metrics.mae),
+            ]);
+        }
+        println!("{}", builder.build().with(Style::modern()));
+
+        if let Some(report) = report {
+            let json = serde_json::json!({ "views": results, "mean": mean });
+            std::fs::write(report, serde_json::to_string_pretty(&json)?)?;
+        }
         Ok(())
     }
 
+    fn print_diff(diff: &SceneDiff) {
+        println!("# Splats: {} / {}", diff.count_a, diff.count_b);
+        println!("# Matched: {}", diff.matched);
+        let mut builder = Builder::default();
+        builder.push_record(["Attribute", "Max", "Mean", "Changed"]);
+        for error in &diff.attributes {
+            builder.push_record([
+                error.attribute.clone(),
+                format!("{}", error.max),
+                format!("{}", error.mean),
+                format!("{}", error.changed),
+            ]);
+        }
+        println!("{}", builder.build().with(Style::modern()));
+        if diff.is_identical() {
+            println!("Scenes are identical");
+        }
+    }
+
+    /// Render both scenes from each camera, writing `view_NN.a.png`, `view_NN.b.png` and a `view_NN.diff.png`
+    /// heatmap of the largest per-channel difference. Returns the PSNR of each view.
+    fn render_diff(
+        a: &[UberSplat],
+        b: &[UberSplat],
+        cameras: &[Camera],
+        output: &Path,
+        heatmap_scale: Option<f32>,
+    ) -> Result<Vec<f32>> {
+        std::fs::create_dir_all(output)?;
+        let renders = cameras
+            .iter()
+            .map(|camera| {
+                let background = Vector3::zeros();
+                (
+                    render_splats(a, camera, &background),
+                    render_splats(b, camera, &background),
+                )
+            })
+            .collect::<Vec<_>>();
+        let differences = renders
+            .iter()
+            .map(|(a, b)| {
+                a.color
+                    .iter()
+                    .zip(&b.color)
+                    .map(|(a, b)| (a - b).abs().max())
+                    .collect::<Vec<f32>>()
+            })
+            .collect::<Vec<_>>();
+        let scale = heatmap_scale.unwrap_or_else(|| {
+            differences
+                .iter()
+                .flatten()
+                .copied()
+                .fold(0.0, f32::max)
+                .max(1.0 / 255.0)
+        });
+        println!("Heatmap white = color difference of {}", scale);
+
+        let mut views_psnr = Vec::new();
+        for (view, ((image_a, image_b), difference)) in renders.iter().zip(&differences).enumerate()
+        {
+            let (width, height) = (image_a.width, image_a.height);
+            let path = |name: &str| output.join(format!("view_{:02}.{}.png", view, name));
+            save_png_rgb8(&path("a"), width, height, &image_a.color, None)?;
+            save_png_rgb8(&path("b"), width, height, &image_b.color, None)?;
+            let heatmap = difference
+                .iter()
+                .map(|d| heatmap_color(d / scale))
+                .collect::<Vec<_>>();
+            save_png_rgb8(&path("diff"), width, height, &heatmap, None)?;
+            let view_psnr = psnr(&image_a.color, &image_b.color);
+            println!("View {}: PSNR {:.3}", view, view_psnr);
+            views_psnr.push(view_psnr);
+        }
+        Ok(views_psnr)
+    }
+
+    fn dump(
+        input: PathBuf,
+        output: OutputFormat,
+        range: Option<IndexRange>,
+        columns: Vec<DumpColumn>,
+    ) -> Result<()> {
+        let splats = load_splats(&input)?;
+        let range = range.map_or(0..splats.len(), |range| range.clamp(splats.len()));
+        let columns = if columns.is_empty() {
+            DumpColumn::value_variants().to_vec()
+        } else {
+            columns
+        };
+        let names = columns.iter().map(|c| c.name()).collect::<Vec<_>>();
+        let rows = range.map(|index| {
+            columns
+                .iter()
+                .map(|column| column.value(index, &splats[index]))
+                .collect()
+        });
+        let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+        write_records(&mut stdout, output, &names, rows)
+    }
+
     fn ply_to_ascii(input: PathBuf, output: PathBuf) -> Result<()> {
-        let mut f = std::fs::File::open(input).unwrap();
+        let mut f = open_input(&input)?;
         let p = ply::parser::Parser::<ply::ply::DefaultElement>::new();
         let mut ply = p.read_ply(&mut f).unwrap();
         ply.header.encoding = ply::ply::Encoding::Ascii;
         let mut buf = Vec::<u8>::new();
         let w = Writer::new();
         w.write_ply(&mut buf, &mut ply).unwrap();
-        std::fs::write(output, &buf).unwrap();
+        write_output(&output, &buf)?;
         Ok(())
     }
 
     fn dump_ply(input: PathBuf) -> Result<()> {
-        let mut f = std::fs::File::open(input).unwrap();
+        let mut f = open_input(&input)?;
         let p = ply::parser::Parser::<ply::ply::DefaultElement>::new();
         let ply = p.read_ply(&mut f).unwrap();
 
@@ -297,19 +1640,30 @@ impl Cli {
         }
         Ok(())
     }
-
 }
 
 fn save_to_json(splats: Vec<UberSplat>, path: &Path) -> Result<()> {
     let json = serde_json::to_string_pretty(&splats)?;
-    std::fs::write(path, json)?;
+    write_output(path, json.as_bytes())?;
     Ok(())
 }
 
-fn modify_splats(input: &Path, output: &Path, closure: impl Fn(&mut Vec<UberSplat>)) -> Result<()> {
+fn modify_splats(
+    input: &Path,
+    output: &Path,
+    closure: impl FnOnce(&mut Vec<UberSplat>),
+) -> Result<()> {
     let mut splats = load_splats(input)?;
     closure(&mut splats);
-    let format = guess_format(output).unwrap();
-    save_splats(splats, format, output)?;
-    Ok(())
+    save_output(splats, output)
+}
+
+/// Save splats in the format implied by `path`, or as JSON for `.json` paths.
+fn save_output(splats: Vec<UberSplat>, path: &Path) -> Result<()> {
+    if inner_path(path).extension() == Some("json".as_ref()) {
+        save_to_json(splats, path)
+    } else {
+        let format = guess_format(path).unwrap();
+        save_splats(splats, format, path)
+    }
 }
@@ -0,0 +1,189 @@
+use crate::*;
+use rand::RngCore;
+use std::sync::Mutex;
+
+/// Largest codebook that `u16` indices can address.
+pub const MAX_CODEBOOK_SIZE: usize = 65536;
+
+/// Training vectors sampled per codebook entry; k-means is trained on a sample and then every vector is assigned.
+const SAMPLES_PER_ENTRY: usize = 32;
+
+/// A k-means codebook of fixed length vectors, stored contiguously.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Codebook {
+    pub dimension: usize,
+    pub entries: Vec<f32>,
+}
+
+fn distance_squared(a: &[f32], b: &[f32], limit: f32) -> f32 {
+    let mut sum = 0.0;
+    for (a, b) in a.iter().zip(b) {
+        sum += (a - b) * (a - b);
+        // Partial sums only grow, so stop once the entry can not be the nearest.
+        if sum >= limit {
+            break;
+        }
+    }
+    sum
+}
+
+/// A uniform random number in [0, 1).
+fn random_unit(rng: &mut impl RngCore) -> f64 {
+    (rng.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+impl Codebook {
+    pub fn len(&self) -> usize {
+        self.entries.len() / self.dimension
+    }
+
+    pub fn entry(&self, index: usize) -> &[f32] {
+        &self.entries[index * self.dimension..(index + 1) * self.dimension]
+    }
+
+    /// The index of the entry nearest to `vector` and its squared distance.
+    pub fn nearest(&self, vector: &[f32]) -> (usize, f32) {
+        let mut best = (0, f32::INFINITY);
+        for (index, entry) in self.entries.chunks_exact(self.dimension).enumerate() {
+            let distance = distance_squared(vector, entry, best.1);
+            if distance < best.1 {
+                best = (index, distance);
+            }
+        }
+        best
+    }
+
+    /// The nearest entry for each of the contiguous `vectors`, computed in parallel.
+    pub fn assign(&self, vectors: &[f32]) -> Vec<u32> {
+        let count = vectors.len() / self.dimension;
+        let mut indices = vec![0; count];
+        let chunk_size = 4096;
+        let chunks = Mutex::new(indices.chunks_mut(chunk_size).enumerate());
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        std::thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    let Some((chunk, indices)) = chunks.lock().unwrap().next() else {
+                        break;
+                    };
+                    for (offset, index) in indices.iter_mut().enumerate() {
+                        let start = (chunk * chunk_size + offset) * self.dimension;
+                        *index = self.nearest(&vectors[start..start + self.dimension]).0 as u32;
+                    }
+                });
+            }
+        });
+        indices
+    }
+}
+
+/// Train a codebook of at most `size` entries on contiguous `vectors` of length `dimension` with k-means++
+/// seeding and `iterations` rounds of Lloyd's algorithm.
+pub fn train_codebook(
+    vectors: &[f32],
+    dimension: usize,
+    size: usize,
+    iterations: usize,
+    rng: &mut SplatRng,
+) -> Codebook {
+    let count = vectors.len() / dimension;
+    let mut order = (0..count).collect::<Vec<usize>>();
+    shuffle_slice(&mut order, rng);
+    order.truncate(size.saturating_mul(SAMPLES_PER_ENTRY));
+    let samples = order
+        .iter()
+        .flat_map(|i| &vectors[i * dimension..(i + 1) * dimension])
+        .copied()
+        .collect::<Vec<f32>>();
+    let sample_count = order.len();
+    let size = size.min(sample_count);
+    if size == 0 {
+        return Codebook {
+            dimension,
+            entries: Vec::new(),
+        };
+    }
+
+    // k-means++: each new entry is a sample chosen with probability proportional to its squared distance from the
+    // nearest existing entry.
+    let sample = |i: usize| &samples[i * dimension..(i + 1) * dimension];
+    let mut entries = sample(random_below(rng, sample_count as u64) as usize).to_vec();
+    let mut distances = (0..sample_count)
+        .map(|i| distance_squared(sample(i), &entries, f32::INFINITY) as f64)
+        .collect::<Vec<f64>>();
+    while entries.len() < size * dimension {
+        let total = distances.iter().sum::<f64>();
+        if total <= 0.0 {
+            // Fewer distinct vectors than entries.
+            break;
+        }
+        let mut target = random_unit(rng) * total;
+        let chosen = distances
+            .iter()
+            .position(|d| {
+                target -= d;
+                target < 0.0
+            })
+            .unwrap_or(sample_count - 1);
+        let entry = sample(chosen).to_vec();
+        for (i, distance) in distances.iter_mut().enumerate() {
+            *distance = distance.min(distance_squared(sample(i), &entry, f32::INFINITY) as f64);
+        }
+        entries.extend(entry);
+    }
+
+    let mut codebook = Codebook { dimension, entries };
+    for _ in 0..iterations {
+        let assignments = codebook.assign(&samples);
+        let mut sums = vec![0.0_f64; codebook.entries.len()];
+        let mut counts = vec![0_usize; codebook.len()];
+        for (i, entry) in assignments.iter().enumerate() {
+            let entry = *entry as usize;
+            counts[entry] += 1;
+            for (sum, value) in sums[entry * dimension..(entry + 1) * dimension]
+                .iter_mut()
+                .zip(sample(i))
+            {
+                *sum += *value as f64;
+            }
+        }
+        for (entry, count) in counts.iter().enumerate() {
+            let values = &mut codebook.entries[entry * dimension..(entry + 1) * dimension];
+            if *count == 0 {
+                // Move empty entries to a random sample rather than wasting them.
+                let replacement = random_below(rng, sample_count as u64) as usize;
+                values.copy_from_slice(sample(replacement));
+            } else {
+                for (value, sum) in values
+                    .iter_mut()
+                    .zip(&sums[entry * dimension..(entry + 1) * dimension])
+                {
+                    *value = (sum / *count as f64) as f32;
+                }
+            }
+        }
+    }
+    codebook
+}
+
+#[cfg(test)]
+#[test]
+fn test_train_codebook_finds_clusters() {
+    let centers = [[0.0, 0.0], [10.0, 0.0], [0.0, 10.0]];
+    let mut rng = splat_rng(Some(7));
+    let vectors = (0..300)
+        .flat_map(|i| {
+            let jitter = (random_unit(&mut rng) as f32 - 0.5) * 0.1;
+            centers[i % 3].map(|v| v + jitter)
+        })
+        .collect::<Vec<f32>>();
+    let codebook = train_codebook(&vectors, 2, 3, 10, &mut splat_rng(Some(1)));
+    assert_eq!(codebook.len(), 3);
+    for center in centers {
+        let (_, distance) = codebook.nearest(&center);
+        assert!(distance < 0.01);
+    }
+    let indices = codebook.assign(&vectors);
+    assert_eq!(indices[0], indices[3]);
+    assert_ne!(indices[0], indices[1]);
+}
@@ -0,0 +1,113 @@
+use crate::*;
+use nalgebra::Vector3;
+use std::str::FromStr;
+
+/// Number of view directions averaged over for `Hemisphere` and `Sphere`.
+const AVERAGE_SAMPLES: usize = 256;
+
+/// Which view the view dependent color of each splat is evaluated for, for formats that hold a single color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorBake {
+    /// Looking along a fixed direction.
+    Direction(Vector3<f32>),
+    /// Seen from a position, each splat along the direction from it.
+    Eye(Vector3<f32>),
+    /// Averaged over view directions within 90 degrees of a direction.
+    Hemisphere(Vector3<f32>),
+    /// Averaged over every view direction.
+    Sphere,
+}
+
+/// Parses `direction=x,y,z`, `eye=x,y,z`, `hemisphere=x,y,z` or `sphere`. Directions are the way the viewer looks.
+impl FromStr for ColorBake {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s.split_once('=').unwrap_or((s, ""));
+        let direction = || {
+            let direction = parse_vector3(value)?;
+            if direction.norm() == 0.0 {
+                return Err(format!("Direction can not be zero in `{}`", s));
+            }
+            Ok(direction.normalize())
+        };
+        match name.trim() {
+            "direction" => Ok(ColorBake::Direction(direction()?)),
+            "eye" => Ok(ColorBake::Eye(parse_vector3(value)?)),
+            "hemisphere" => Ok(ColorBake::Hemisphere(direction()?)),
+            "sphere" if value.is_empty() => Ok(ColorBake::Sphere),
+            _ => Err(format!(
+                "Expected `direction=x,y,z`, `eye=x,y,z`, `hemisphere=x,y,z` or `sphere`, got `{}`",
+                s
+            )),
+        }
+    }
+}
+
+/// Replace spherical harmonic colors with the color a viewer shows for `bake`, returning the number of splats changed.
+///
+/// Averages are of the displayed color, which is clamped at zero, rather than just the DC term.
+pub fn bake_colors(splats: &mut [UberSplat], bake: &ColorBake) -> usize {
+    let directions = match bake {
+        ColorBake::Hemisphere(center) => fibonacci_sphere(AVERAGE_SAMPLES)
+            .into_iter()
+            .filter(|d| d.dot(center) > 0.0)
+            .collect(),
+        ColorBake::Sphere => fibonacci_sphere(AVERAGE_SAMPLES),
+        ColorBake::Direction(direction) => vec![*direction],
+        ColorBake::Eye(_) => Vec::new(),
+    };
+    let mut baked = 0;
+    for splat in splats {
+        if !matches!(splat.color, Color::SphericalHarmonic(..)) {
+            continue;
+        }
+        let color = match bake {
+            ColorBake::Eye(eye) => {
+                let direction = splat.position - eye;
+                if direction.norm() > 0.0 {
+                    splat.color.evaluate(&direction.normalize())
+                } else {
+                    splat.color.evaluate(&Vector3::z())
+                }
+            }
+            _ => {
+                directions
+                    .iter()
+                    .map(|d| splat.color.evaluate(d))
+                    .sum::<Vector3<f32>>()
+                    / directions.len() as f32
+            }
+        };
+        splat.color = Color::FirstOrderSphericalHarmonic(color.map(|v| (v - 0.5) / SH_C0));
+        baked += 1;
+    }
+    baked
+}
+
+#[cfg(test)]
+#[test]
+fn test_bake_colors_matches_evaluated_sh() {
+    // Only the first band's y coefficient of the red channel is set, so red depends on the view's y component.
+    let mut rest = vec![0.0; 45];
+    rest[0] = 0.5;
+    let splat = UberSplat::new(
+        Vector3::zeros(),
+        None,
+        Color::SphericalHarmonic(Vector3::zeros(), rest),
+        Opacity::LinearFloat(1.0),
+        Scale::LinearFloat(Vector3::repeat(0.1)),
+        nalgebra::Quaternion::identity(),
+    );
+    let direction = "direction=0,-2,0".parse::<ColorBake>().unwrap();
+    let mut splats = [splat.clone(), splat.clone()];
+    assert_eq!(bake_colors(&mut splats[..1], &direction), 1);
+    let expected = splat.color.evaluate(&-Vector3::y());
+    assert!((splats[0].color.to_linear_float() - expected).norm() < 1e-6);
+    assert!(splats[0].color.to_linear_float().x > 0.6);
+
+    // Opposite directions cancel out over the sphere.
+    bake_colors(&mut splats[1..], &ColorBake::Sphere);
+    assert!((splats[1].color.to_linear_float() - Vector3::repeat(0.5)).norm() < 1e-3);
+    assert!("sphere=1".parse::<ColorBake>().is_err());
+}
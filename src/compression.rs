@@ -0,0 +1,162 @@
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Level used when saving compressed files, or 0 for each codec's default.
+static COMPRESSION_LEVEL: AtomicU32 = AtomicU32::new(0);
+
+/// Set the level used by `create_output`: 1 to 9 for gzip and 1 to 22 for zstd, or 0 for the codec's default.
+pub fn set_compression_level(level: u32) {
+    COMPRESSION_LEVEL.store(level, Ordering::Relaxed);
+}
+
+/// A compression wrapped around a splat file, chosen by a trailing `.gz` or `.zst` extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn from_path(path: &Path) -> Compression {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+}
+
+/// The path without any compression extension, so `scene.splat.gz` gives `scene.splat`, whose extension names the
+/// splat format.
+pub fn inner_path(path: &Path) -> PathBuf {
+    match Compression::from_path(path) {
+        Compression::None => path.to_path_buf(),
+        _ => path.with_extension(""),
+    }
+}
+
+/// Open `path` for reading, decompressing it if it is compressed.
+pub fn open_input(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let file = File::open(path)?;
+    Ok(match Compression::from_path(path) {
+        Compression::None => Box::new(BufReader::new(file)),
+        Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(file))),
+        Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::new(file)?)),
+    })
+}
+
+/// Read all of `path`, decompressing it if it is compressed.
+pub fn read_input(path: &Path) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    open_input(path)?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// The size of the contents of `path` after decompression.
+pub fn input_size(path: &Path) -> io::Result<u64> {
+    match Compression::from_path(path) {
+        Compression::None => Ok(std::fs::metadata(path)?.len()),
+        _ => io::copy(&mut open_input(path)?, &mut io::sink()),
+    }
+}
+
+/// A file being written, compressed according to its extension. `finish` must be called to complete the file.
+pub enum OutputFile {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl OutputFile {
+    pub fn finish(self) -> io::Result<()> {
+        let mut writer = match self {
+            OutputFile::Plain(writer) => writer,
+            OutputFile::Gzip(encoder) => encoder.finish()?,
+            OutputFile::Zstd(encoder) => encoder.finish()?,
+        };
+        writer.flush()
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            OutputFile::Plain(writer) => writer,
+            OutputFile::Gzip(encoder) => encoder,
+            OutputFile::Zstd(encoder) => encoder,
+        }
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer().flush()
+    }
+}
+
+/// Check that the compression level set with `set_compression_level` suits the compression of `path`.
+pub fn check_compression_level(path: &Path) -> io::Result<()> {
+    let level = COMPRESSION_LEVEL.load(Ordering::Relaxed);
+    if Compression::from_path(path) == Compression::Gzip && level > 9 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Gzip compression level {} is above 9", level),
+        ));
+    }
+    Ok(())
+}
+
+/// Create `path` for writing, compressing it if its extension asks for it.
+pub fn create_output(path: &Path) -> io::Result<OutputFile> {
+    check_compression_level(path)?;
+    let level = COMPRESSION_LEVEL.load(Ordering::Relaxed);
+    let compression = Compression::from_path(path);
+    let file = BufWriter::new(File::create(path)?);
+    Ok(match compression {
+        Compression::None => OutputFile::Plain(file),
+        Compression::Gzip => {
+            let level = match level {
+                0 => flate2::Compression::default(),
+                level => flate2::Compression::new(level),
+            };
+            OutputFile::Gzip(GzEncoder::new(file, level))
+        }
+        Compression::Zstd => OutputFile::Zstd(zstd::Encoder::new(file, level as i32)?),
+    })
+}
+
+/// Write `data` to `path`, compressing it if its extension asks for it.
+pub fn write_output(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut output = create_output(path)?;
+    output.write_all(data)?;
+    output.finish()
+}
+
+#[cfg(test)]
+#[test]
+fn test_compressed_round_trip() {
+    let directory = std::env::temp_dir().join(format!("compression-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let data = (0..10000).map(|i| (i % 7) as u8).collect::<Vec<u8>>();
+    for name in ["scene.splat", "scene.splat.gz", "scene.ply.zst"] {
+        let path = directory.join(name);
+        write_output(&path, &data).unwrap();
+        assert_eq!(read_input(&path).unwrap(), data);
+        assert_eq!(input_size(&path).unwrap(), data.len() as u64);
+        if Compression::from_path(&path) != Compression::None {
+            assert!(std::fs::metadata(&path).unwrap().len() < data.len() as u64 / 10);
+        }
+    }
+    assert_eq!(
+        inner_path(Path::new("a/scene.ply.zst")),
+        Path::new("a/scene.ply")
+    );
+    std::fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,116 @@
+use crate::*;
+use clap::ValueEnum;
+use nalgebra::{Vector2, Vector3};
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum DensityMode {
+    /// Number of splats per cell
+    Count,
+    /// Sum of linear opacities per cell
+    Opacity,
+    /// Opacity weighted mean color per cell
+    Color,
+}
+
+/// Splats accumulated on a grid perpendicular to an axis.
+#[derive(Debug, Clone)]
+pub struct DensityMap {
+    pub width: usize,
+    pub height: usize,
+    /// World position of the corner of the first cell, along the image's column and row axes.
+    pub origin: Vector2<f32>,
+    pub cell_size: f32,
+    pub count: Vec<u32>,
+    pub opacity: Vec<f32>,
+    /// Sum of opacity weighted colors.
+    pub color: Vec<Vector3<f32>>,
+}
+
+/// The two axes spanning the plane perpendicular to `axis`, used as image columns and rows.
+pub fn plane_axes(axis: Axis) -> (usize, usize) {
+    match axis {
+        Axis::X => (1, 2),
+        Axis::Y => (0, 2),
+        Axis::Z => (0, 1),
+    }
+}
+
+/// Project splat positions orthographically along `axis` onto a grid with `resolution` cells along its longer side.
+pub fn density_map(splats: &[UberSplat], axis: Axis, resolution: usize) -> DensityMap {
+    let (column_axis, row_axis) = plane_axes(axis);
+    let (min, max) = find_bounds(splats);
+    let origin = Vector2::new(min[column_axis], min[row_axis]);
+    let extent = Vector2::new(max[column_axis], max[row_axis]) - origin;
+    let cell_size = (extent.max() / resolution as f32).max(f32::EPSILON);
+    let width = ((extent.x / cell_size).ceil() as usize).clamp(1, resolution);
+    let height = ((extent.y / cell_size).ceil() as usize).clamp(1, resolution);
+
+    let mut map = DensityMap {
+        width,
+        height,
+        origin,
+        cell_size,
+        count: vec![0; width * height],
+        opacity: vec![0.0; width * height],
+        color: vec![Vector3::zeros(); width * height],
+    };
+    for splat in splats {
+        let cell = |index: usize, origin: f32, size: usize| {
+            (((splat.position[index] - origin) / cell_size) as usize).min(size - 1)
+        };
+        let index = cell(row_axis, origin.y, height) * width + cell(column_axis, origin.x, width);
+        let opacity = splat.opacity.to_linear_float();
+        map.count[index] += 1;
+        map.opacity[index] += opacity;
+        map.color[index] += splat.color.to_linear_float() * opacity;
+    }
+    map
+}
+
+impl DensityMap {
+    /// RGB pixels and a coverage alpha for a mode. Counts and opacities are shown as a heatmap, normalised to the
+    /// fullest cell, on a log scale unless `linear` is set.
+    pub fn to_image(&self, mode: DensityMode, linear: bool) -> (Vec<Vector3<f32>>, Vec<f32>) {
+        let alpha = self
+            .count
+            .iter()
+            .map(|c| if *c > 0 { 1.0 } else { 0.0 })
+            .collect();
+        let values = match mode {
+            DensityMode::Count => self.count.iter().map(|c| *c as f32).collect::<Vec<_>>(),
+            DensityMode::Opacity => self.opacity.clone(),
+            DensityMode::Color => {
+                let color = self
+                    .color
+                    .iter()
+                    .zip(&self.opacity)
+                    .map(|(c, o)| if *o > 0.0 { c / *o } else { Vector3::zeros() })
+                    .collect();
+                return (color, alpha);
+            }
+        };
+        let scale = |v: f32| if linear { v } else { v.ln_1p() };
+        let max = scale(values.iter().copied().fold(0.0, f32::max)).max(f32::EPSILON);
+        let color = values
+            .iter()
+            .map(|v| heatmap_color(scale(*v) / max))
+            .collect();
+        (color, alpha)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_density_map_counts_cells() {
+    let splat = |x: f32, z: f32| UberSplat::test_splat(Vector3::new(x, 5.0, z));
+    let splats = vec![
+        splat(0.0, 0.0),
+        splat(0.1, 0.2),
+        splat(4.0, 2.0),
+        splat(3.9, 1.9),
+    ];
+    let map = density_map(&splats, Axis::Y, 4);
+    assert_eq!((map.width, map.height), (4, 2));
+    assert_eq!(map.count, vec![2, 0, 0, 0, 0, 0, 0, 2]);
+    assert_eq!(map.opacity[7], 1.0);
+}
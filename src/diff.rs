@@ -0,0 +1,308 @@
+use crate::*;
+use clap::ValueEnum;
+use nalgebra::{UnitQuaternion, Vector3};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// How splats in one scene are paired with splats in the other.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum DiffMatching {
+    /// Pair splats at the same index
+    Index,
+    /// Pair each splat with the nearest splat in the other scene
+    Nearest,
+}
+
+/// Maximum and mean error of one attribute over all matched splats.
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributeError {
+    pub attribute: String,
+    pub max: f32,
+    pub mean: f32,
+    /// Number of matched pairs that differ at all.
+    pub changed: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SceneDiff {
+    pub count_a: usize,
+    pub count_b: usize,
+    pub matched: usize,
+    pub attributes: Vec<AttributeError>,
+}
+
+impl SceneDiff {
+    pub fn is_identical(&self) -> bool {
+        self.count_a == self.count_b && self.attributes.iter().all(|a| a.changed == 0)
+    }
+}
+
+/// A uniform grid over splat positions for nearest neighbour queries.
+struct PositionGrid<'a> {
+    positions: Vec<&'a Vector3<f32>>,
+    min: Vector3<f32>,
+    cell_size: f32,
+    cells: HashMap<[i64; 3], Vec<usize>>,
+    /// Largest cell coordinate on each axis; the smallest is zero.
+    max_cell: [i64; 3],
+}
+
+impl<'a> PositionGrid<'a> {
+    fn new(splats: &'a [UberSplat]) -> PositionGrid<'a> {
+        let (min, max) = find_bounds(splats);
+        // Aim for a couple of splats per occupied cell.
+        let extent = (max - min).max().max(f32::EPSILON);
+        let cell_size = extent / (splats.len() as f32 / 2.0).cbrt().max(1.0);
+        let mut grid = PositionGrid {
+            positions: splats.iter().map(|s| &s.position).collect(),
+            min,
+            cell_size,
+            cells: HashMap::new(),
+            max_cell: [0; 3],
+        };
+        for index in 0..splats.len() {
+            let cell = grid.cell(grid.positions[index]);
+            for (max, value) in grid.max_cell.iter_mut().zip(cell) {
+                *max = (*max).max(value);
+            }
+            grid.cells.entry(cell).or_default().push(index);
+        }
+        grid
+    }
+
+    fn cell(&self, position: &Vector3<f32>) -> [i64; 3] {
+        let cell = ((position - self.min) / self.cell_size).map(|v| v.floor() as i64);
+        [cell.x, cell.y, cell.z]
+    }
+
+    /// Call `visit` for each cell `ring` steps from `center` on some axis, clipped to the grid. Only the faces of the
+    /// ring's cube are visited, so each ring costs its surface rather than its volume.
+    fn visit_ring(&self, center: [i64; 3], ring: i64, mut visit: impl FnMut([i64; 3])) {
+        let range = |axis: usize| {
+            (center[axis] - ring).max(0)..=(center[axis] + ring).min(self.max_cell[axis])
+        };
+        for x in range(0) {
+            for y in range(1) {
+                if (x - center[0]).abs() == ring || (y - center[1]).abs() == ring {
+                    for z in range(2) {
+                        visit([x, y, z]);
+                    }
+                } else {
+                    for z in [center[2] - ring, center[2] + ring] {
+                        if range(2).contains(&z) {
+                            visit([x, y, z]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Index of the nearest position, searching shells of cells outwards until no closer point can exist.
+    fn nearest(&self, position: &Vector3<f32>) -> Option<usize> {
+        if self.positions.is_empty() {
+            return None;
+        }
+        let center = self.cell(position);
+        // Rings nearer than the grid's bounding box hold no cells, so start at the first one that touches it.
+        let distance_to_grid = |axis: usize| {
+            (-center[axis])
+                .max(center[axis] - self.max_cell[axis])
+                .max(0)
+        };
+        let first_ring = (0..3).map(distance_to_grid).max()?;
+        let max_ring = (0..3)
+            .map(|axis| {
+                center[axis]
+                    .abs()
+                    .max((self.max_cell[axis] - center[axis]).abs())
+            })
+            .max()?;
+        let mut best: Option<(usize, f32)> = None;
+        for ring in first_ring..=max_ring {
+            if let Some((_, distance)) = best {
+                if distance < ((ring - 1) as f32 * self.cell_size).powi(2) {
+                    break;
+                }
+            }
+            self.visit_ring(center, ring, |cell| {
+                for index in self.cells.get(&cell).into_iter().flatten() {
+                    let distance = (self.positions[*index] - position).norm_squared();
+                    if best.is_none_or(|(_, d)| distance < d) {
+                        best = Some((*index, distance));
+                    }
+                }
+            });
+        }
+        best.map(|(index, _)| index)
+    }
+}
+
+/// Pairs of indices into `a` and `b`.
+pub fn match_splats(
+    a: &[UberSplat],
+    b: &[UberSplat],
+    matching: DiffMatching,
+) -> Vec<(usize, usize)> {
+    match matching {
+        DiffMatching::Index => (0..a.len().min(b.len())).map(|i| (i, i)).collect(),
+        DiffMatching::Nearest => {
+            if b.is_empty() {
+                return Vec::new();
+            }
+            let grid = PositionGrid::new(b);
+            a.iter()
+                .enumerate()
+                .filter_map(|(i, splat)| grid.nearest(&splat.position).map(|j| (i, j)))
+                .collect()
+        }
+    }
+}
+
+/// Per-attribute differences between two splats, in the order of `DIFF_ATTRIBUTES`.
+fn splat_errors(a: &UberSplat, b: &UberSplat) -> [Option<f32>; 6] {
+    // `angle_to` uses acos, which is imprecise near zero, so equal rotations are special cased.
+    let rotation = if a.rotation == b.rotation {
+        0.0
+    } else {
+        let rotation_a = UnitQuaternion::from_quaternion(a.rotation);
+        rotation_a
+            .angle_to(&UnitQuaternion::from_quaternion(b.rotation))
+            .to_degrees()
+    };
+    let scale_a = a.scale.to_linear_float();
+    let scale_b = b.scale.to_linear_float();
+    let scale = (0..3)
+        .map(|i| {
+            (scale_a[i] - scale_b[i]).abs()
+                / scale_a[i].abs().max(scale_b[i].abs()).max(f32::EPSILON)
+        })
+        .fold(0.0, f32::max);
+    let sh_rest = match (&a.color, &b.color) {
+        (Color::SphericalHarmonic(_, rest_a), Color::SphericalHarmonic(_, rest_b)) => {
+            let degree = sh_degree_for_rest_len(rest_a.len().max(rest_b.len())).unwrap_or(0);
+            let rest_a = resize_sh_rest(rest_a, degree);
+            let rest_b = resize_sh_rest(rest_b, degree);
+            Some(
+                rest_a
+                    .iter()
+                    .zip(&rest_b)
+                    .map(|(a, b)| (a - b).abs())
+                    .fold(0.0, f32::max),
+            )
+        }
+        _ => None,
+    };
+    [
+        Some((a.position - b.position).norm()),
+        Some(
+            (a.color.to_linear_float() - b.color.to_linear_float())
+                .abs()
+                .max(),
+        ),
+        sh_rest,
+        Some((a.opacity.to_linear_float() - b.opacity.to_linear_float()).abs()),
+        Some(scale),
+        Some(rotation),
+    ]
+}
+
+/// Attribute names and units reported by `diff_scenes`.
+pub const DIFF_ATTRIBUTES: [&str; 6] = [
+    "position (distance)",
+    "color (linear)",
+    "sh rest",
+    "opacity (linear)",
+    "scale (relative)",
+    "rotation (degrees)",
+];
+
+/// Compare two scenes attribute by attribute after pairing their splats.
+pub fn diff_scenes(a: &[UberSplat], b: &[UberSplat], matching: DiffMatching) -> SceneDiff {
+    let pairs = match_splats(a, b, matching);
+    let mut sums = [(0.0_f64, 0.0_f32, 0_usize, 0_usize); 6];
+    for (i, j) in &pairs {
+        for (sum, error) in sums.iter_mut().zip(splat_errors(&a[*i], &b[*j])) {
+            if let Some(error) = error {
+                sum.0 += error as f64;
+                sum.1 = sum.1.max(error);
+                sum.2 += (error > 0.0) as usize;
+                sum.3 += 1;
+            }
+        }
+    }
+    let attributes = DIFF_ATTRIBUTES
+        .iter()
+        .zip(sums)
+        .filter(|(_, (_, _, _, count))| *count > 0)
+        .map(|(attribute, (sum, max, changed, count))| AttributeError {
+            attribute: attribute.to_string(),
+            max,
+            mean: (sum / count as f64) as f32,
+            changed,
+        })
+        .collect();
+    SceneDiff {
+        count_a: a.len(),
+        count_b: b.len(),
+        matched: pairs.len(),
+        attributes,
+    }
+}
+
+/// Map a value in [0, 1] to a black, red, yellow, white heatmap color.
+pub fn heatmap_color(value: f32) -> Vector3<f32> {
+    let v = value.clamp(0.0, 1.0) * 3.0;
+    Vector3::new(
+        v.min(1.0),
+        (v - 1.0).clamp(0.0, 1.0),
+        (v - 2.0).clamp(0.0, 1.0),
+    )
+}
+
+#[cfg(test)]
+#[test]
+fn test_nearest_matching_ignores_order() {
+    let a = (0..50)
+        .map(|i| UberSplat::test_splat(Vector3::new(i as f32, i as f32 * 0.5, 0.0)))
+        .collect::<Vec<_>>();
+    let mut b = a.iter().rev().cloned().collect::<Vec<_>>();
+    b[0].opacity = Opacity::LinearFloat(0.75);
+
+    let diff = diff_scenes(&a, &b, DiffMatching::Nearest);
+    assert_eq!(diff.matched, 50);
+    let opacity = diff
+        .attributes
+        .iter()
+        .find(|a| a.attribute.starts_with("opacity"))
+        .unwrap();
+    assert_eq!(opacity.changed, 1);
+    assert!((opacity.max - 0.25).abs() < 1e-6);
+    assert_eq!(diff.attributes[0].max, 0.0);
+    assert!(!diff_scenes(&a, &b, DiffMatching::Index).is_identical());
+}
+
+#[cfg(test)]
+#[test]
+fn test_nearest_finds_far_outliers() {
+    // Ten thousand splats in a unit cube give cells of about 0.06, so the outliers are millions of cells away.
+    let b = (0..10000)
+        .map(|i| {
+            let position = Vector3::new((i % 20) as f32, (i / 20 % 20) as f32, (i / 400) as f32);
+            UberSplat::test_splat(position / 25.0)
+        })
+        .collect::<Vec<_>>();
+    let grid = PositionGrid::new(&b);
+    let far = Vector3::new(1e5, 0.5, -2e5);
+    let expected = (0..b.len())
+        .min_by(|i, j| {
+            let distance = |k: usize| (b[k].position - far).norm_squared();
+            distance(*i).total_cmp(&distance(*j))
+        })
+        .unwrap();
+    assert_eq!(grid.nearest(&far), Some(expected));
+    assert_eq!(
+        grid.nearest(&Vector3::new(0.41, 0.39, 0.4)),
+        Some(10 * 400 + 10 * 20 + 10)
+    );
+}
@@ -0,0 +1,142 @@
+use crate::*;
+use nalgebra::{Matrix3, Rotation3, Unit, Vector3};
+
+/// Rec. 709 luminance weights, for linear light.
+const LUMINANCE: Vector3<f32> = Vector3::new(0.2126, 0.7152, 0.0722);
+
+/// Step used to differentiate the grade for spherical harmonic coefficients.
+const DERIVATIVE_STEP: f32 = 1e-3;
+
+/// A color grade. Stored colors are treated as the sRGB encoded values a viewer displays; exposure, white balance,
+/// saturation and hue are applied in linear light and gamma to the encoded value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grade {
+    /// Exposure change in stops.
+    pub exposure: f32,
+    /// Per channel gains.
+    pub white_balance: Vector3<f32>,
+    /// 0 for grey, 1 for unchanged.
+    pub saturation: f32,
+    /// Rotation about the grey axis in degrees.
+    pub hue: f32,
+    /// Encoded values are raised to `1 / gamma`, so values above 1 brighten the mid tones.
+    pub gamma: f32,
+}
+
+impl Default for Grade {
+    fn default() -> Self {
+        Grade {
+            exposure: 0.0,
+            white_balance: Vector3::repeat(1.0),
+            saturation: 1.0,
+            hue: 0.0,
+            gamma: 1.0,
+        }
+    }
+}
+
+impl Grade {
+    /// The linear light part of the grade as a matrix.
+    fn linear_matrix(&self) -> Matrix3<f32> {
+        let gain = Matrix3::from_diagonal(&(self.white_balance * self.exposure.exp2()));
+        let luminance = Matrix3::from_rows(&[LUMINANCE.transpose(); 3]);
+        let saturation = luminance + (Matrix3::identity() - luminance) * self.saturation;
+        let axis = Unit::new_normalize(Vector3::repeat(1.0));
+        let hue = Rotation3::from_axis_angle(&axis, self.hue.to_radians()).into_inner();
+        hue * saturation * gain
+    }
+
+    /// Grade an encoded color, given the grade's `linear_matrix`.
+    fn apply(&self, matrix: &Matrix3<f32>, rgb: &Vector3<f32>) -> Vector3<f32> {
+        let linear = matrix * rgb.map(srgb_to_linear);
+        linear
+            .map(linear_to_srgb)
+            .map(|v| v.max(0.0).powf(1.0 / self.gamma))
+    }
+
+    /// How the graded color changes with the encoded color around `rgb`, by central differences.
+    fn jacobian(&self, matrix: &Matrix3<f32>, rgb: &Vector3<f32>) -> Matrix3<f32> {
+        let mut jacobian = Matrix3::zeros();
+        for channel in 0..3 {
+            let mut step = Vector3::zeros();
+            step[channel] = DERIVATIVE_STEP;
+            let difference = self.apply(matrix, &(rgb + step)) - self.apply(matrix, &(rgb - step));
+            jacobian.set_column(channel, &(difference / (2.0 * DERIVATIVE_STEP)));
+        }
+        jacobian
+    }
+
+    /// Grade a color of any kind. The DC term of spherical harmonics is graded exactly and the higher bands are
+    /// scaled by the grade's slope at the DC color, so view dependent variation changes consistently with it.
+    pub fn grade_color(&self, color: &Color) -> Color {
+        let matrix = self.linear_matrix();
+        let sh_dc = |rgb: Vector3<f32>| rgb.map(|v| (v - 0.5) / SH_C0);
+        match color {
+            Color::SphericalHarmonic(dc, rest) => {
+                let base = dc.map(|v| 0.5 + SH_C0 * v);
+                let jacobian = self.jacobian(&matrix, &base);
+                let count = rest.len() / 3;
+                let mut graded = vec![0.0; rest.len()];
+                for k in 0..count {
+                    let coefficient = Vector3::new(rest[k], rest[count + k], rest[2 * count + k]);
+                    let coefficient = jacobian * coefficient;
+                    for channel in 0..3 {
+                        graded[channel * count + k] = coefficient[channel];
+                    }
+                }
+                Color::SphericalHarmonic(sh_dc(self.apply(&matrix, &base)), graded)
+            }
+            Color::FirstOrderSphericalHarmonic(dc) => {
+                let base = dc.map(|v| 0.5 + SH_C0 * v);
+                Color::FirstOrderSphericalHarmonic(sh_dc(self.apply(&matrix, &base)))
+            }
+            Color::LinearFloat(rgb) => {
+                Color::LinearFloat(self.apply(&matrix, rgb).map(|v| v.clamp(0.0, 1.0)))
+            }
+            Color::LinearU8(_) => Color::LinearU8(
+                self.apply(&matrix, &color.to_linear_float())
+                    .map(unit_to_u8),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_grade_color() {
+    let apply = |grade: &Grade, rgb: &Vector3<f32>| grade.apply(&grade.linear_matrix(), rgb);
+    // One stop doubles linear light.
+    let exposure = Grade {
+        exposure: 1.0,
+        ..Default::default()
+    };
+    let graded = apply(&exposure, &Vector3::repeat(linear_to_srgb(0.2)));
+    assert!((graded.map(srgb_to_linear) - Vector3::repeat(0.4)).norm() < 1e-5);
+
+    // Zero saturation gives grey and the identity grade changes nothing.
+    let desaturate = Grade {
+        saturation: 0.0,
+        ..Default::default()
+    };
+    let grey = apply(&desaturate, &Vector3::new(0.9, 0.2, 0.1));
+    assert!((grey.x - grey.y).abs() < 1e-6 && (grey.y - grey.z).abs() < 1e-6);
+    let rgb = Vector3::new(0.7, 0.3, 0.5);
+    assert!((apply(&Grade::default(), &rgb) - rgb).norm() < 1e-6);
+
+    // Spherical harmonics follow the graded color in every direction, to first order.
+    let mut rest = vec![0.0; 45];
+    rest[0] = 0.02;
+    rest[20] = -0.03;
+    let color = Color::SphericalHarmonic(Vector3::new(0.3, -0.2, 0.1), rest);
+    let grade = Grade {
+        exposure: 0.5,
+        hue: 30.0,
+        gamma: 1.2,
+        ..Default::default()
+    };
+    let graded = grade.grade_color(&color);
+    for direction in fibonacci_sphere(16) {
+        let expected = apply(&grade, &color.evaluate(&direction));
+        assert!((graded.evaluate(&direction) - expected).norm() < 2e-3);
+    }
+}
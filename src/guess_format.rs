@@ -6,11 +6,14 @@ use std::{
     path::Path,
 };
 
-#[derive(Debug, EnumIter)]
+#[derive(Debug, Clone, Copy, EnumIter)]
 pub enum SplatFormats {
     SplatA,
     SplatB,
     SplatC,
+    SplatVq,
+    SplatX,
+    Splat2d,
 }
 
 impl SplatFormats {
@@ -19,6 +22,17 @@ impl SplatFormats {
             SplatFormats::SplatA => "The original `.ply` based splat format as defined by - <https://repo-sam.inria.fr/fungraph/3d-gaussian-splatting/> - with optional normals and spherical harmonics. XXX bytes pers splat.",
             SplatFormats::SplatB => "`.splat` format as used by antimatter15's splat viewer: <https://github.com/antimatter15/splat>. 32 bytes per splat.",
             SplatFormats::SplatC => "`.splatc` half float format as defined by Sean Cier's MetalSplatter <https://github.com/scier/MetalSplatter> project. 26 bytes per splat.",
+            SplatFormats::SplatVq => "`.splatvq` format with k-means codebooks for spherical harmonics and optionally scales and rotations, as in CompactGS and LightGaussian.",
+            SplatFormats::SplatX => "`.splatx` format with a float32, float16, fixed point or 8 bit codec per attribute, recorded in its header.",
+            SplatFormats::Splat2d => "`.ply` surfels written by 2D Gaussian Splatting <https://surfsplatting.github.io/>, with two scales and the normal along the rotation's third axis.",
+        }
+    }
+
+    /// How the format encodes colors; loading and saving convert to and from the sRGB colors held by `Color`.
+    pub fn color_space(&self) -> ColorSpace {
+        match self {
+            SplatFormats::SplatC => ColorSpace::Linear,
+            _ => ColorSpace::Srgb,
         }
     }
 }
@@ -28,6 +42,9 @@ pub fn guess_format(path: &Path) -> Option<SplatFormats> {
         (SplatFormats::SplatA, SplatA::is_format(path)),
         (SplatFormats::SplatB, SplatB::is_format(path)),
         (SplatFormats::SplatC, SplatC::is_format(path)),
+        (SplatFormats::SplatVq, SplatVq::is_format(path)),
+        (SplatFormats::SplatX, SplatX::is_format(path)),
+        (SplatFormats::Splat2d, Splat2d::is_format(path)),
     ];
 
     ordered_results.sort_by(|a, b| match (&a.1, &b.1) {
@@ -0,0 +1,147 @@
+use anyhow::Result;
+use byteorder::{LittleEndian, WriteBytesExt};
+use nalgebra::Vector3;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Write `[0, 1]` RGB values as an 8 bit PNG, with an alpha channel if `alpha` is given.
+pub fn save_png_rgb8(
+    path: &Path,
+    width: usize,
+    height: usize,
+    pixels: &[Vector3<f32>],
+    alpha: Option<&[f32]>,
+) -> Result<()> {
+    let mut data = Vec::with_capacity(pixels.len() * 4);
+    for (index, pixel) in pixels.iter().enumerate() {
+        data.extend(pixel.iter().map(|v| to_u8(*v)));
+        if let Some(alpha) = alpha {
+            data.push(to_u8(alpha[index]));
+        }
+    }
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+    encoder.set_color(if alpha.is_some() {
+        png::ColorType::Rgba
+    } else {
+        png::ColorType::Rgb
+    });
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    Ok(())
+}
+
+/// Write `[0, 1]` values with `channels` interleaved channels (1 or 3) as a 16 bit PNG.
+pub fn save_png16(
+    path: &Path,
+    width: usize,
+    height: usize,
+    channels: usize,
+    values: &[f32],
+) -> Result<()> {
+    let data = values
+        .iter()
+        .flat_map(|v| ((v.clamp(0.0, 1.0) * 65535.0).round() as u16).to_be_bytes())
+        .collect::<Vec<u8>>();
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+    encoder.set_color(match channels {
+        1 => png::ColorType::Grayscale,
+        3 => png::ColorType::Rgb,
+        _ => anyhow::bail!("Unsupported channel count {}", channels),
+    });
+    encoder.set_depth(png::BitDepth::Sixteen);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    Ok(())
+}
+
+/// Write unclamped float values with `channels` interleaved channels (1 or 3) as a little endian PFM file.
+pub fn save_pfm(
+    path: &Path,
+    width: usize,
+    height: usize,
+    channels: usize,
+    values: &[f32],
+) -> Result<()> {
+    let kind = match channels {
+        1 => "Pf",
+        3 => "PF",
+        _ => anyhow::bail!("Unsupported channel count {}", channels),
+    };
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(file, "{}\n{} {}\n-1.0\n", kind, width, height)?;
+    // PFM rows run bottom to top.
+    for row in values.chunks(width * channels).rev() {
+        for value in row {
+            file.write_f32::<LittleEndian>(*value)?;
+        }
+    }
+    Ok(())
+}
+
+/// An 8 bit per channel image converted to `[0, 1]` RGB.
+#[derive(Debug, Clone)]
+pub struct LoadedImage {
+    pub width: usize,
+    pub height: usize,
+    pub color: Vec<Vector3<f32>>,
+}
+
+/// Load a PNG or JPEG image. Images with an alpha channel are composited over `background`.
+pub fn load_image(path: &Path, background: &Vector3<f32>) -> Result<LoadedImage> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let (width, height, channels, data) = match extension.as_str() {
+        "png" => {
+            let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+            decoder.set_transformations(png::Transformations::normalize_to_color8());
+            let mut reader = decoder.read_info()?;
+            let mut data = vec![0; reader.output_buffer_size()];
+            let info = reader.next_frame(&mut data)?;
+            data.truncate(info.buffer_size());
+            let channels = info.color_type.samples();
+            (info.width as usize, info.height as usize, channels, data)
+        }
+        "jpg" | "jpeg" => {
+            let mut decoder = jpeg_decoder::Decoder::new(BufReader::new(File::open(path)?));
+            let data = decoder.decode()?;
+            let info = decoder.info().unwrap();
+            let channels = match info.pixel_format {
+                jpeg_decoder::PixelFormat::L8 => 1,
+                jpeg_decoder::PixelFormat::RGB24 => 3,
+                format => anyhow::bail!("Unsupported JPEG pixel format {:?}", format),
+            };
+            (info.width as usize, info.height as usize, channels, data)
+        }
+        _ => anyhow::bail!("Unsupported image type: {}", path.display()),
+    };
+
+    let color = data
+        .chunks_exact(channels)
+        .map(|pixel| {
+            let value = |i: usize| pixel[i] as f32 / 255.0;
+            let (rgb, alpha) = match channels {
+                1 => (Vector3::repeat(value(0)), 1.0),
+                2 => (Vector3::repeat(value(0)), value(1)),
+                3 => (Vector3::new(value(0), value(1), value(2)), 1.0),
+                _ => (Vector3::new(value(0), value(1), value(2)), value(3)),
+            };
+            rgb * alpha + background * (1.0 - alpha)
+        })
+        .collect();
+    Ok(LoadedImage {
+        width,
+        height,
+        color,
+    })
+}
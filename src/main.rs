@@ -1,23 +1,73 @@
 use anyhow::Result;
 
+mod adjust;
+mod animation;
+mod cameras;
 mod cli;
+mod codebook;
+mod color_bake;
+mod compression;
+mod density_map;
+mod diff;
+mod grade;
 mod guess_format;
+mod images;
+mod merge;
+mod output;
+mod metrics;
+mod splat_2d;
 mod splat_a;
 mod splat_b;
 mod splat_c;
+mod splat_vq;
+mod splat_x;
+mod random;
+mod render;
+mod repair;
+mod spherical_harmonics;
 mod splat_format;
+mod statistics;
+mod sorting;
 mod support;
+mod tiling;
 mod uber_splat;
+mod validate;
+mod vector_quantisation;
 mod actions;
 
+use adjust::*;
+use animation::*;
+use cameras::*;
 use cli::*;
+use codebook::*;
+use color_bake::*;
+use compression::*;
+use density_map::*;
+use diff::*;
+use grade::*;
 use guess_format::*;
+use images::*;
+use merge::*;
+use metrics::*;
+use output::*;
+use splat_2d::*;
 use splat_a::*;
 use splat_b::*;
 use splat_c::*;
+use splat_vq::*;
+use splat_x::*;
+use random::*;
+use render::*;
+use repair::*;
+use spherical_harmonics::*;
 use splat_format::*;
+use statistics::*;
+use sorting::*;
 use support::*;
+use tiling::*;
 use uber_splat::*;
+use validate::*;
+use vector_quantisation::*;
 use actions::*;
 
 fn main() -> Result<()> {
@@ -0,0 +1,133 @@
+use crate::*;
+use nalgebra::{DMatrix, UnitQuaternion, Vector3};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// A similarity transform applied to a whole scene: uniform scale, then rotation, then translation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneTransform {
+    pub translation: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+    pub scale: f32,
+}
+
+impl Default for SceneTransform {
+    fn default() -> Self {
+        SceneTransform {
+            translation: Vector3::zeros(),
+            rotation: UnitQuaternion::identity(),
+            scale: 1.0,
+        }
+    }
+}
+
+/// Parses `identity` or `;` separated terms: `translate=x,y,z`, `rotate=x,y,z` (XYZ euler angles in degrees) and `scale=s`.
+impl FromStr for SceneTransform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut transform = SceneTransform::default();
+        if s.trim() == "identity" {
+            return Ok(transform);
+        }
+        for term in s.split(';').map(str::trim).filter(|t| !t.is_empty()) {
+            let (name, value) = term
+                .split_once('=')
+                .ok_or(format!("Expected `name=value`, got `{}`", term))?;
+            let values = value
+                .split(',')
+                .map(|v| v.trim().parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|e| format!("Invalid number in `{}`: {}", term, e))?;
+            match (name.trim(), values.as_slice()) {
+                ("translate", [x, y, z]) => transform.translation = Vector3::new(*x, *y, *z),
+                ("rotate", [x, y, z]) => {
+                    transform.rotation = UnitQuaternion::from_euler_angles(
+                        x.to_radians(),
+                        y.to_radians(),
+                        z.to_radians(),
+                    )
+                }
+                ("scale", [s]) if *s > 0.0 => transform.scale = *s,
+                _ => return Err(format!("Unknown or malformed transform term `{}`", term)),
+            }
+        }
+        Ok(transform)
+    }
+}
+
+/// Apply a transform to the position, orientation, scale, normal and view dependent color of each splat.
+pub fn transform_splats(splats: &mut [UberSplat], transform: &SceneTransform) {
+    let mut sh_rotations: HashMap<usize, Vec<DMatrix<f32>>> = HashMap::new();
+    for splat in splats {
+        splat.position =
+            transform.translation + transform.rotation * (splat.position * transform.scale);
+        splat.rotation = transform.rotation.into_inner() * splat.rotation;
+        splat.scale = match splat.scale {
+            Scale::Exponent(v) => Scale::Exponent(v.add_scalar(transform.scale.ln())),
+            Scale::LinearFloat(v) => Scale::LinearFloat(v * transform.scale),
+        };
+        splat.normal = splat.normal.map(|n| transform.rotation * n);
+        if let Color::SphericalHarmonic(_, rest) = &mut splat.color {
+            if let Some(degree) = sh_degree_for_rest_len(rest.len()) {
+                let matrices = sh_rotations
+                    .entry(degree)
+                    .or_insert_with(|| sh_rotation_matrices(&transform.rotation, degree));
+                *rest = rotate_sh_rest(rest, matrices);
+            }
+        }
+    }
+}
+
+/// Bring every splat's color into one encoding.
+///
+/// If a degree is given, or any splat carries spherical harmonics, all colors become spherical harmonics of that
+/// degree (defaulting to the highest degree present). Otherwise mixed linear encodings become `LinearFloat`.
+pub fn reconcile_colors(splats: &mut [UberSplat], sh_degree: Option<usize>) {
+    let degree = sh_degree.or_else(|| splats.iter().filter_map(|s| s.color.sh_degree()).max());
+    match degree {
+        Some(degree) => {
+            for splat in splats {
+                if splat.color.sh_degree() != Some(degree) {
+                    splat.color = splat.color.to_spherical_harmonic(degree);
+                }
+            }
+        }
+        None => {
+            if splats.iter().all(|s| matches!(s.color, Color::LinearU8(_))) {
+                return;
+            }
+            for splat in splats {
+                splat.color = Color::LinearFloat(splat.color.to_linear_float());
+            }
+        }
+    }
+}
+
+/// Transform each scene and combine them into one with a consistent color encoding.
+pub fn merge_scenes(
+    scenes: Vec<(Vec<UberSplat>, SceneTransform)>,
+    sh_degree: Option<usize>,
+) -> Vec<UberSplat> {
+    let mut merged = Vec::new();
+    for (mut splats, transform) in scenes {
+        if transform != SceneTransform::default() {
+            transform_splats(&mut splats, &transform);
+        }
+        merged.extend(splats);
+    }
+    reconcile_colors(&mut merged, sh_degree);
+    merged
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_scene_transform() {
+    let transform: SceneTransform = "translate=1,2,3; rotate=0,90,0; scale=2".parse().unwrap();
+    assert_eq!(transform.translation, Vector3::new(1.0, 2.0, 3.0));
+    assert_eq!(transform.scale, 2.0);
+    let rotated = transform.rotation * Vector3::x();
+    assert!((rotated - Vector3::new(0.0, 0.0, -1.0)).norm() < 1e-6);
+    assert!("scale=-1".parse::<SceneTransform>().is_err());
+    assert!("identity".parse::<SceneTransform>().unwrap() == SceneTransform::default());
+}
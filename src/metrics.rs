@@ -0,0 +1,160 @@
+use nalgebra::Vector3;
+use serde::Serialize;
+
+/// Image quality of one rendered view against its ground truth.
+#[derive(Debug, Clone, Serialize)]
+pub struct ViewMetrics {
+    pub name: String,
+    /// Peak signal to noise ratio in dB.
+    pub psnr: f32,
+    pub ssim: f32,
+    /// Mean absolute error per channel, in [0, 1].
+    pub mae: f32,
+}
+
+impl ViewMetrics {
+    /// The mean over all views. PSNR is averaged per view in dB, as is usual when reporting results.
+    pub fn mean(views: &[ViewMetrics]) -> ViewMetrics {
+        let mean = |value: fn(&ViewMetrics) -> f32| {
+            views.iter().map(value).sum::<f32>() / views.len() as f32
+        };
+        ViewMetrics {
+            name: "Mean".to_string(),
+            psnr: mean(|v| v.psnr),
+            ssim: mean(|v| v.ssim),
+            mae: mean(|v| v.mae),
+        }
+    }
+}
+
+/// Peak signal to noise ratio, in dB, between two `[0, 1]` RGB images.
+pub fn psnr(a: &[Vector3<f32>], b: &[Vector3<f32>]) -> f32 {
+    let squared_error = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).norm_squared() as f64)
+        .sum::<f64>();
+    let mse = squared_error / (a.len() * 3) as f64;
+    if mse == 0.0 {
+        f32::INFINITY
+    } else {
+        (10.0 * (1.0 / mse).log10()) as f32
+    }
+}
+
+/// Mean absolute error between two RGB images.
+pub fn mean_absolute_error(a: &[Vector3<f32>], b: &[Vector3<f32>]) -> f32 {
+    let error = a
+        .iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).abs().sum() as f64)
+        .sum::<f64>();
+    (error / (a.len() * 3) as f64) as f32
+}
+
+/// Convolve a single channel image with a separable kernel, zero padded to the same size.
+fn blur(values: &[f32], width: usize, height: usize, kernel: &[f32]) -> Vec<f32> {
+    let radius = kernel.len() / 2;
+    let sample = |values: &[f32], i: isize, len: usize, stride: usize, base: usize| {
+        if i < 0 || i as usize >= len {
+            0.0
+        } else {
+            values[base + i as usize * stride]
+        }
+    };
+    let mut horizontal = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            horizontal[y * width + x] = kernel
+                .iter()
+                .enumerate()
+                .map(|(k, w)| {
+                    w * sample(
+                        values,
+                        (x + k) as isize - radius as isize,
+                        width,
+                        1,
+                        y * width,
+                    )
+                })
+                .sum();
+        }
+    }
+    let mut result = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            result[y * width + x] = kernel
+                .iter()
+                .enumerate()
+                .map(|(k, w)| {
+                    w * sample(
+                        &horizontal,
+                        (y + k) as isize - radius as isize,
+                        height,
+                        width,
+                        x,
+                    )
+                })
+                .sum();
+        }
+    }
+    result
+}
+
+/// Per-pixel structural similarity, averaged over the RGB channels.
+///
+/// Uses an 11x11 gaussian window with sigma 1.5 and zero padding, matching the SSIM used to evaluate 3DGS.
+pub fn ssim_map(a: &[Vector3<f32>], b: &[Vector3<f32>], width: usize, height: usize) -> Vec<f32> {
+    const C1: f32 = 0.01 * 0.01;
+    const C2: f32 = 0.03 * 0.03;
+    let kernel = (0..11)
+        .map(|i| (-((i as f32 - 5.0).powi(2)) / (2.0 * 1.5 * 1.5)).exp())
+        .collect::<Vec<f32>>();
+    let total = kernel.iter().sum::<f32>();
+    let kernel = kernel.iter().map(|k| k / total).collect::<Vec<f32>>();
+
+    let mut map = vec![0.0; a.len()];
+    for channel in 0..3 {
+        let x = a.iter().map(|p| p[channel]).collect::<Vec<f32>>();
+        let y = b.iter().map(|p| p[channel]).collect::<Vec<f32>>();
+        let product =
+            |p: &[f32], q: &[f32]| p.iter().zip(q).map(|(p, q)| p * q).collect::<Vec<f32>>();
+        let mu_x = blur(&x, width, height, &kernel);
+        let mu_y = blur(&y, width, height, &kernel);
+        let xx = blur(&product(&x, &x), width, height, &kernel);
+        let yy = blur(&product(&y, &y), width, height, &kernel);
+        let xy = blur(&product(&x, &y), width, height, &kernel);
+        for i in 0..map.len() {
+            let sigma_x = xx[i] - mu_x[i] * mu_x[i];
+            let sigma_y = yy[i] - mu_y[i] * mu_y[i];
+            let sigma_xy = xy[i] - mu_x[i] * mu_y[i];
+            let value = ((2.0 * mu_x[i] * mu_y[i] + C1) * (2.0 * sigma_xy + C2))
+                / ((mu_x[i] * mu_x[i] + mu_y[i] * mu_y[i] + C1) * (sigma_x + sigma_y + C2));
+            map[i] += value / 3.0;
+        }
+    }
+    map
+}
+
+pub fn ssim(a: &[Vector3<f32>], b: &[Vector3<f32>], width: usize, height: usize) -> f32 {
+    let map = ssim_map(a, b, width, height);
+    (map.iter().map(|v| *v as f64).sum::<f64>() / map.len() as f64) as f32
+}
+
+#[cfg(test)]
+#[test]
+fn test_metrics_of_identical_and_noisy_images() {
+    let (width, height) = (24, 16);
+    let a = (0..width * height)
+        .map(|i| Vector3::repeat((i % 7) as f32 / 7.0))
+        .collect::<Vec<_>>();
+    assert_eq!(psnr(&a, &a), f32::INFINITY);
+    assert!((ssim(&a, &a, width, height) - 1.0).abs() < 1e-5);
+    let b = a
+        .iter()
+        .enumerate()
+        .map(|(i, p)| p.add_scalar(if i % 2 == 0 { 0.1 } else { -0.1 }))
+        .collect::<Vec<_>>();
+    assert!((psnr(&a, &b) - 20.0).abs() < 1e-3);
+    assert!(ssim(&a, &b, width, height) < 0.99);
+}
@@ -0,0 +1,217 @@
+use crate::*;
+use anyhow::Result;
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+use std::io::Write;
+use std::str::FromStr;
+use tabled::builder::Builder;
+use tabled::settings::Style;
+
+/// How commands that report on a scene print their results.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable tables
+    Table,
+    /// A single JSON document
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Comma separated values with a header row
+    Csv,
+}
+
+/// A half open range of splat indices, written `start..end`, `start..` or `..end`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndexRange {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl FromStr for IndexRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once("..")
+            .ok_or_else(|| format!("Expected start..end, got {}", s))?;
+        let parse = |v: &str| {
+            v.trim()
+                .parse::<usize>()
+                .map_err(|e| format!("Invalid index {}: {}", v, e))
+        };
+        let start = if start.is_empty() { 0 } else { parse(start)? };
+        let end = if end.is_empty() {
+            None
+        } else {
+            Some(parse(end)?)
+        };
+        if end.is_some_and(|end| end < start) {
+            return Err(format!("Range end is before its start: {}", s));
+        }
+        Ok(IndexRange { start, end })
+    }
+}
+
+impl IndexRange {
+    /// The range clamped to `len` items.
+    pub fn clamp(&self, len: usize) -> std::ops::Range<usize> {
+        let end = self.end.unwrap_or(len).min(len);
+        self.start.min(end)..end
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum DumpColumn {
+    Index,
+    X,
+    Y,
+    Z,
+    Nx,
+    Ny,
+    Nz,
+    /// Linear color of the DC term
+    R,
+    G,
+    B,
+    /// Linear opacity
+    Opacity,
+    /// Linear scale
+    ScaleX,
+    ScaleY,
+    ScaleZ,
+    RotW,
+    RotX,
+    RotY,
+    RotZ,
+    /// Spherical harmonic degree, empty for plain colors
+    ShDegree,
+}
+
+impl DumpColumn {
+    pub fn name(&self) -> String {
+        self.to_possible_value()
+            .unwrap()
+            .get_name()
+            .replace('-', "_")
+    }
+
+    pub fn value(&self, index: usize, splat: &UberSplat) -> Value {
+        let float = f32_value;
+        let normal = |i: usize| splat.normal.map_or(Value::Null, |n| float(n[i]));
+        match self {
+            DumpColumn::Index => Value::from(index),
+            DumpColumn::X => float(splat.position.x),
+            DumpColumn::Y => float(splat.position.y),
+            DumpColumn::Z => float(splat.position.z),
+            DumpColumn::Nx => normal(0),
+            DumpColumn::Ny => normal(1),
+            DumpColumn::Nz => normal(2),
+            DumpColumn::R => float(splat.color.to_linear_float().x),
+            DumpColumn::G => float(splat.color.to_linear_float().y),
+            DumpColumn::B => float(splat.color.to_linear_float().z),
+            DumpColumn::Opacity => float(splat.opacity.to_linear_float()),
+            DumpColumn::ScaleX => float(splat.scale.to_linear_float().x),
+            DumpColumn::ScaleY => float(splat.scale.to_linear_float().y),
+            DumpColumn::ScaleZ => float(splat.scale.to_linear_float().z),
+            DumpColumn::RotW => float(splat.rotation.w),
+            DumpColumn::RotX => float(splat.rotation.i),
+            DumpColumn::RotY => float(splat.rotation.j),
+            DumpColumn::RotZ => float(splat.rotation.k),
+            DumpColumn::ShDegree => splat.color.sh_degree().map_or(Value::Null, Value::from),
+        }
+    }
+}
+
+/// A JSON number with the shortest decimal representation of an `f32`, rather than that of its widened `f64`.
+/// Non-finite values become null.
+pub fn f32_value(value: f32) -> Value {
+    value
+        .to_string()
+        .parse::<f64>()
+        .map_or(Value::Null, Value::from)
+}
+
+fn csv_field(value: &Value) -> String {
+    let text = match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    };
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+fn table_field(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Write rows of values under `columns`. Rows are streamed, except for tables which need every row to size columns.
+pub fn write_records(
+    writer: &mut impl Write,
+    format: OutputFormat,
+    columns: &[String],
+    rows: impl Iterator<Item = Vec<Value>>,
+) -> Result<()> {
+    let object =
+        |row: Vec<Value>| Value::Object(columns.iter().cloned().zip(row).collect::<Map<_, _>>());
+    match format {
+        OutputFormat::Table => {
+            let mut builder = Builder::default();
+            builder.push_record(columns);
+            for row in rows {
+                builder.push_record(row.iter().map(table_field));
+            }
+            writeln!(writer, "{}", builder.build().with(Style::modern()))?;
+        }
+        OutputFormat::Json => {
+            write!(writer, "[")?;
+            for (index, row) in rows.enumerate() {
+                let separator = if index == 0 { "" } else { "," };
+                write!(writer, "{}\n  {}", separator, object(row))?;
+            }
+            writeln!(writer, "\n]")?;
+        }
+        OutputFormat::Ndjson => {
+            for row in rows {
+                writeln!(writer, "{}", object(row))?;
+            }
+        }
+        OutputFormat::Csv => {
+            writeln!(writer, "{}", columns.join(","))?;
+            for row in rows {
+                let fields = row.iter().map(csv_field).collect::<Vec<_>>();
+                writeln!(writer, "{}", fields.join(","))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[test]
+fn test_index_range_and_csv_output() {
+    let range = "2..5".parse::<IndexRange>().unwrap();
+    assert_eq!(range.clamp(4), 2..4);
+    assert_eq!("..3".parse::<IndexRange>().unwrap().clamp(10), 0..3);
+    assert_eq!("7..".parse::<IndexRange>().unwrap().clamp(5), 5..5);
+    assert!("5..2".parse::<IndexRange>().is_err());
+
+    let mut buffer = Vec::new();
+    let columns = ["name".to_string(), "value".to_string()];
+    let rows = vec![
+        vec![Value::from("a, b"), Value::from(1.5)],
+        vec![Value::from("c"), Value::Null],
+    ];
+    write_records(&mut buffer, OutputFormat::Csv, &columns, rows.into_iter()).unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "name,value\n\"a, b\",1.5\nc,\n"
+    );
+}
@@ -0,0 +1,44 @@
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// The random number generator used by every randomised operation.
+///
+/// ChaCha8's output stream is specified and value-stable, and the helpers below only consume raw `u64`s, so a
+/// given seed produces identical results on every platform and release.
+pub type SplatRng = ChaCha8Rng;
+
+/// A generator seeded with `seed`, or from system entropy when no seed is given.
+pub fn splat_rng(seed: Option<u64>) -> SplatRng {
+    match seed {
+        Some(seed) => SplatRng::seed_from_u64(seed),
+        None => SplatRng::from_entropy(),
+    }
+}
+
+/// An unbiased random integer in `0..bound`.
+pub fn random_below(rng: &mut impl RngCore, bound: u64) -> u64 {
+    assert!(bound > 0);
+    let limit = u64::MAX - u64::MAX % bound;
+    loop {
+        let value = rng.next_u64();
+        if value < limit {
+            return value % bound;
+        }
+    }
+}
+
+/// Fisher-Yates shuffle.
+pub fn shuffle_slice<T>(items: &mut [T], rng: &mut impl RngCore) {
+    for i in (1..items.len()).rev() {
+        let j = random_below(rng, i as u64 + 1) as usize;
+        items.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_seeded_shuffle_is_fixed() {
+    let mut items = (0..10).collect::<Vec<u32>>();
+    shuffle_slice(&mut items, &mut splat_rng(Some(42)));
+    assert_eq!(items, vec![1, 5, 9, 6, 3, 2, 0, 8, 4, 7]);
+}
@@ -0,0 +1,410 @@
+use crate::*;
+use clap::ValueEnum;
+use nalgebra::{Isometry3, Matrix2x3, Matrix3, Point3, Vector2, Vector3};
+use std::sync::Mutex;
+
+/// Edge length, in pixels, of the screen tiles splats are binned into.
+const TILE_SIZE: usize = 16;
+
+/// A pinhole camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    /// World to view transform. The camera looks down the view space -Z axis with +Y up.
+    pub view: Isometry3<f32>,
+    /// Focal lengths in pixels.
+    pub focal: Vector2<f32>,
+    /// Principal point in pixels, from the top left corner.
+    pub principal_point: Vector2<f32>,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Camera {
+    /// A camera at `eye` looking at `target`, with square pixels, a centered principal point and a vertical field of
+    /// view in degrees.
+    pub fn look_at(
+        eye: &Vector3<f32>,
+        target: &Vector3<f32>,
+        up: &Vector3<f32>,
+        fov_y: f32,
+        width: usize,
+        height: usize,
+    ) -> Camera {
+        let focal = (height as f32 / 2.0) / (fov_y.to_radians() / 2.0).tan();
+        Camera {
+            view: Isometry3::look_at_rh(&Point3::from(*eye), &Point3::from(*target), up),
+            focal: Vector2::new(focal, focal),
+            principal_point: Vector2::new(width as f32 / 2.0, height as f32 / 2.0),
+            width,
+            height,
+        }
+    }
+
+    /// Camera position in world space.
+    pub fn eye(&self) -> Vector3<f32> {
+        self.view.inverse().translation.vector
+    }
+}
+
+/// A splat projected to screen space, ready for compositing.
+#[derive(Debug, Clone)]
+pub struct ProjectedSplat {
+    /// Index of the splat in the scene.
+    pub index: usize,
+    pub center: Vector2<f32>,
+    /// Inverse of the 2D covariance, stored as (xx, xy, yy).
+    pub conic: Vector3<f32>,
+    pub depth: f32,
+    pub radius: f32,
+    pub color: Vector3<f32>,
+    pub opacity: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderedImage {
+    pub width: usize,
+    pub height: usize,
+    pub color: Vec<Vector3<f32>>,
+    /// Accumulated opacity of each pixel.
+    pub alpha: Vec<f32>,
+}
+
+/// Per-pixel geometry buffers.
+#[derive(Debug, Clone)]
+pub struct RenderedGeometry {
+    pub width: usize,
+    pub height: usize,
+    /// Expected view space depth, zero where nothing was hit.
+    pub depth: Vec<f32>,
+    pub alpha: Vec<f32>,
+    /// World space unit normals facing the camera, zero where nothing was hit.
+    pub normal: Vec<Vector3<f32>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum RenderPass {
+    Color,
+    Depth,
+    Alpha,
+    Normal,
+}
+
+pub fn covariance_matrix(splat: &UberSplat) -> Matrix3<f32> {
+    let (a, b) = splat.to_cov();
+    Matrix3::new(a.x, a.y, a.z, a.y, b.x, b.y, a.z, b.y, b.z)
+}
+
+/// Project a splat with the EWA approximation used by the reference 3DGS rasterizer.
+pub fn project_splat(
+    index: usize,
+    splat: &UberSplat,
+    camera: &Camera,
+    eye: &Vector3<f32>,
+) -> Option<ProjectedSplat> {
+    let t = camera.view.transform_point(&Point3::from(splat.position));
+    let depth = -t.z;
+    if depth <= 0.2 {
+        return None;
+    }
+
+    let (fx, fy) = (camera.focal.x, camera.focal.y);
+    let (cx, cy) = (camera.principal_point.x, camera.principal_point.y);
+    let limit_x = 1.3 * (camera.width as f32 / 2.0) / fx;
+    let limit_y = 1.3 * (camera.height as f32 / 2.0) / fy;
+    let x = (t.x / depth).clamp(-limit_x, limit_x) * depth;
+    let y = (t.y / depth).clamp(-limit_y, limit_y) * depth;
+
+    let jacobian = Matrix2x3::new(
+        fx / depth,
+        0.0,
+        fx * x / (depth * depth),
+        0.0,
+        -fy / depth,
+        -fy * y / (depth * depth),
+    );
+    let rotation = camera.view.rotation.to_rotation_matrix();
+    let transform = jacobian * rotation.matrix();
+    let mut cov2d = transform * covariance_matrix(splat) * transform.transpose();
+    // Low-pass filter so every splat covers at least about a pixel.
+    cov2d[(0, 0)] += 0.3;
+    cov2d[(1, 1)] += 0.3;
+
+    let (a, b, c) = (cov2d[(0, 0)], cov2d[(0, 1)], cov2d[(1, 1)]);
+    let determinant = a * c - b * b;
+    if determinant <= 0.0 || !determinant.is_finite() {
+        return None;
+    }
+    let conic = Vector3::new(c / determinant, -b / determinant, a / determinant);
+    let middle = 0.5 * (a + c);
+    let largest_eigenvalue = middle + (middle * middle - determinant).max(0.1).sqrt();
+    let radius = (3.0 * largest_eigenvalue.sqrt()).ceil();
+
+    let center = Vector2::new(fx * t.x / depth + cx, -fy * t.y / depth + cy);
+    if center.x + radius < 0.0
+        || center.y + radius < 0.0
+        || center.x - radius > camera.width as f32
+        || center.y - radius > camera.height as f32
+    {
+        return None;
+    }
+
+    let direction = (splat.position - eye).normalize();
+    Some(ProjectedSplat {
+        index,
+        center,
+        conic,
+        depth,
+        radius,
+        color: splat.color.evaluate(&direction),
+        opacity: splat.opacity.to_linear_float(),
+    })
+}
+
+/// Weight of a projected splat at a pixel center, before opacity. `None` when the splat does not contribute.
+pub fn gaussian_weight(splat: &ProjectedSplat, pixel: &Vector2<f32>) -> Option<f32> {
+    let d = splat.center - pixel;
+    let power =
+        -0.5 * (splat.conic.x * d.x * d.x + splat.conic.z * d.y * d.y) - splat.conic.y * d.x * d.y;
+    if power > 0.0 {
+        None
+    } else {
+        Some(power.exp())
+    }
+}
+
+/// Project all splats and bin them into screen tiles, each sorted front to back.
+pub fn bin_splats(splats: &[UberSplat], camera: &Camera) -> (Vec<ProjectedSplat>, Vec<Vec<u32>>) {
+    let eye = camera.eye();
+    let projected = splats
+        .iter()
+        .enumerate()
+        .filter_map(|(index, splat)| project_splat(index, splat, camera, &eye))
+        .collect::<Vec<ProjectedSplat>>();
+
+    let tiles_x = camera.width.div_ceil(TILE_SIZE);
+    let tiles_y = camera.height.div_ceil(TILE_SIZE);
+    let mut tiles = vec![Vec::new(); tiles_x * tiles_y];
+    for (index, splat) in projected.iter().enumerate() {
+        let tile_range = |center: f32, count: usize| {
+            let start = ((center - splat.radius) / TILE_SIZE as f32)
+                .floor()
+                .max(0.0) as usize;
+            let end = ((center + splat.radius) / TILE_SIZE as f32).ceil().max(0.0) as usize;
+            start.min(count)..end.min(count)
+        };
+        for ty in tile_range(splat.center.y, tiles_y) {
+            for tx in tile_range(splat.center.x, tiles_x) {
+                tiles[ty * tiles_x + tx].push(index as u32);
+            }
+        }
+    }
+    for tile in &mut tiles {
+        tile.sort_by(|a, b| {
+            projected[*a as usize]
+                .depth
+                .total_cmp(&projected[*b as usize].depth)
+        });
+    }
+    (projected, tiles)
+}
+
+/// Run `shade` for every pixel, in parallel over rows of tiles. `shade` receives the pixel center and the pixel's
+/// tile's front to back list of splats, and returns the pixel's value.
+pub fn rasterize<T: Clone + Send>(
+    camera: &Camera,
+    tiles: &[Vec<u32>],
+    initial: T,
+    shade: impl Fn(&Vector2<f32>, &[u32]) -> T + Sync,
+) -> Vec<T> {
+    let width = camera.width;
+    let tiles_x = width.div_ceil(TILE_SIZE);
+    let mut pixels = vec![initial; width * camera.height];
+    let bands = Mutex::new(pixels.chunks_mut(width * TILE_SIZE).enumerate());
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let Some((ty, band)) = bands.lock().unwrap().next() else {
+                    break;
+                };
+                for (row, band_row) in band.chunks_mut(width).enumerate() {
+                    let y = ty * TILE_SIZE + row;
+                    for (x, pixel) in band_row.iter_mut().enumerate() {
+                        let tile = &tiles[ty * tiles_x + x / TILE_SIZE];
+                        let center = Vector2::new(x as f32 + 0.5, y as f32 + 0.5);
+                        *pixel = shade(&center, tile);
+                    }
+                }
+            });
+        }
+    });
+    pixels
+}
+
+/// Front to back alpha compositing as in the reference rasterizer. Calls `visit` with each contributing splat and
+/// its weight (alpha times remaining transmittance), and returns the final transmittance.
+pub fn composite(
+    projected: &[ProjectedSplat],
+    tile: &[u32],
+    pixel: &Vector2<f32>,
+    mut visit: impl FnMut(&ProjectedSplat, f32),
+) -> f32 {
+    let mut transmittance = 1.0;
+    for index in tile {
+        let splat = &projected[*index as usize];
+        let Some(weight) = gaussian_weight(splat, pixel) else {
+            continue;
+        };
+        let alpha = (splat.opacity * weight).min(0.99);
+        if alpha < 1.0 / 255.0 {
+            continue;
+        }
+        let next = transmittance * (1.0 - alpha);
+        if next < 0.0001 {
+            break;
+        }
+        visit(splat, alpha * transmittance);
+        transmittance = next;
+    }
+    transmittance
+}
+
+/// Render splats to an image with the standard 3DGS pipeline, on the CPU.
+pub fn render_splats(
+    splats: &[UberSplat],
+    camera: &Camera,
+    background: &Vector3<f32>,
+) -> RenderedImage {
+    let (projected, tiles) = bin_splats(splats, camera);
+    let pixels = rasterize(camera, &tiles, (Vector3::zeros(), 0.0), |pixel, tile| {
+        let mut color = Vector3::zeros();
+        let transmittance = composite(&projected, tile, pixel, |splat, weight| {
+            color += splat.color * weight;
+        });
+        (color + background * transmittance, 1.0 - transmittance)
+    });
+    let (color, alpha) = pixels.into_iter().unzip();
+    RenderedImage {
+        width: camera.width,
+        height: camera.height,
+        color,
+        alpha,
+    }
+}
+
+/// Render expected depth, accumulated opacity and blended normals.
+pub fn render_geometry(splats: &[UberSplat], camera: &Camera) -> RenderedGeometry {
+    let (projected, tiles) = bin_splats(splats, camera);
+    let eye = camera.eye();
+    let mut normals = vec![Vector3::zeros(); splats.len()];
+    for p in &projected {
+        let splat = &splats[p.index];
+        let normal = splat.surface_normal();
+        let facing = normal.dot(&(eye - splat.position)) >= 0.0;
+        normals[p.index] = if facing { normal } else { -normal };
+    }
+    let pixels = rasterize(
+        camera,
+        &tiles,
+        (0.0, 0.0, Vector3::zeros()),
+        |pixel, tile| {
+            let mut depth = 0.0;
+            let mut normal = Vector3::zeros();
+            let transmittance = composite(&projected, tile, pixel, |splat, weight| {
+                depth += splat.depth * weight;
+                normal += normals[splat.index] * weight;
+            });
+            let alpha = 1.0 - transmittance;
+            if alpha > 0.0 {
+                (
+                    depth / alpha,
+                    alpha,
+                    normal.try_normalize(1e-12).unwrap_or_default(),
+                )
+            } else {
+                (0.0, 0.0, Vector3::zeros())
+            }
+        },
+    );
+    let mut geometry = RenderedGeometry {
+        width: camera.width,
+        height: camera.height,
+        depth: Vec::with_capacity(pixels.len()),
+        alpha: Vec::with_capacity(pixels.len()),
+        normal: Vec::with_capacity(pixels.len()),
+    };
+    for (depth, alpha, normal) in pixels {
+        geometry.depth.push(depth);
+        geometry.alpha.push(alpha);
+        geometry.normal.push(normal);
+    }
+    geometry
+}
+
+/// A camera distance that frames most of the scene: the 90th percentile distance of splats from `center`, scaled
+/// to fit a 50 degree field of view.
+pub fn framing_distance(splats: &[UberSplat], center: &Vector3<f32>) -> f32 {
+    let mut distances = splats
+        .iter()
+        .map(|splat| (splat.position - center).norm())
+        .collect::<Vec<f32>>();
+    if distances.is_empty() {
+        return 1.0;
+    }
+    distances.sort_by(f32::total_cmp);
+    let radius = distances[(distances.len() - 1) * 9 / 10];
+    (radius * 2.5).max(0.5)
+}
+
+#[cfg(test)]
+#[test]
+fn test_render_single_splat_covers_center() {
+    use nalgebra::Quaternion;
+    let splat = UberSplat::new(
+        Vector3::zeros(),
+        None,
+        Color::LinearFloat(Vector3::new(1.0, 0.0, 0.0)),
+        Opacity::LinearFloat(1.0),
+        Scale::LinearFloat(Vector3::repeat(0.1)),
+        Quaternion::identity(),
+    );
+    let camera = Camera::look_at(
+        &Vector3::new(0.0, 0.0, 2.0),
+        &Vector3::zeros(),
+        &Vector3::y(),
+        50.0,
+        32,
+        32,
+    );
+    let image = render_splats(&[splat], &camera, &Vector3::zeros());
+    let center = image.color[16 * 32 + 16];
+    assert!(center.x > 0.9 && center.y < 0.01);
+    assert!(image.alpha[0] < 0.01);
+}
+
+#[cfg(test)]
+#[test]
+fn test_render_geometry_of_flat_splat() {
+    use nalgebra::Quaternion;
+    let splat = UberSplat::new(
+        Vector3::zeros(),
+        None,
+        Color::LinearFloat(Vector3::repeat(1.0)),
+        Opacity::LinearFloat(1.0),
+        Scale::LinearFloat(Vector3::new(0.2, 0.2, 0.001)),
+        Quaternion::identity(),
+    );
+    let camera = Camera::look_at(
+        &Vector3::new(0.0, 0.0, -2.0),
+        &Vector3::zeros(),
+        &Vector3::y(),
+        50.0,
+        32,
+        32,
+    );
+    let geometry = render_geometry(&[splat], &camera);
+    let center = 16 * 32 + 16;
+    assert!((geometry.depth[center] - 2.0).abs() < 1e-3);
+    assert!((geometry.normal[center] - Vector3::new(0.0, 0.0, -1.0)).norm() < 1e-3);
+    assert_eq!(geometry.depth[0], 0.0);
+}
@@ -0,0 +1,277 @@
+use crate::*;
+use nalgebra::{Quaternion, Vector3};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::Hasher;
+
+/// Number of example indices kept for each kind of change.
+const MAX_EXAMPLES: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct RepairOptions {
+    /// Smallest allowed linear scale.
+    pub min_scale: f32,
+    /// Largest allowed linear scale, defaulting to the length of the scene's bounding box diagonal or 1, whichever is
+    /// larger. Scenes with no extent have no default.
+    pub max_scale: Option<f32>,
+    pub drop_duplicates: bool,
+}
+
+/// One kind of change, how many splats it was applied to and the input indices of the first few.
+#[derive(Debug, Clone, Serialize)]
+pub struct RepairChange {
+    pub category: String,
+    pub count: usize,
+    pub indices: Vec<usize>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct RepairLog {
+    pub changes: Vec<RepairChange>,
+}
+
+impl RepairLog {
+    fn record(&mut self, category: &str, index: usize) {
+        match self.changes.iter_mut().find(|c| c.category == category) {
+            Some(change) => {
+                change.count += 1;
+                if change.indices.len() < MAX_EXAMPLES {
+                    change.indices.push(index);
+                }
+            }
+            None => self.changes.push(RepairChange {
+                category: category.to_string(),
+                count: 1,
+                indices: vec![index],
+            }),
+        }
+    }
+}
+
+/// Clamp each component of a linear scale, keeping the splat's scale representation.
+pub fn clamp_scale(scale: &Scale, min: f32, max: f32) -> Option<Scale> {
+    let linear = scale.to_linear_float();
+    let clamped = linear.map(|v| v.clamp(min, max));
+    if clamped == linear {
+        return None;
+    }
+    Some(scale.with_linear(clamped))
+}
+
+/// The bit patterns of every stored value, with a tag for each representation and `-0.0` folded into `0.0`.
+fn canonical_bits(splat: &UberSplat) -> impl Iterator<Item = u32> + '_ {
+    fn bits(v: &f32) -> u32 {
+        (v + 0.0).to_bits()
+    }
+    let normal = splat.normal.iter().flat_map(|n| n.iter().map(bits));
+    let color: Box<dyn Iterator<Item = u32>> = match &splat.color {
+        Color::SphericalHarmonic(dc, rest) => {
+            Box::new([0].into_iter().chain(dc.iter().chain(rest).map(bits)))
+        }
+        Color::FirstOrderSphericalHarmonic(dc) => {
+            Box::new([1].into_iter().chain(dc.iter().map(bits)))
+        }
+        Color::LinearFloat(rgb) => Box::new([2].into_iter().chain(rgb.iter().map(bits))),
+        Color::LinearU8(rgb) => Box::new([3].into_iter().chain(rgb.iter().map(|v| *v as u32))),
+    };
+    let opacity = match splat.opacity {
+        Opacity::LinearFloat(v) => [0, bits(&v)],
+        Opacity::LinearU8(v) => [1, v as u32],
+        Opacity::LogitFloat(v) => [2, bits(&v)],
+    };
+    let scale = match &splat.scale {
+        Scale::Exponent(v) => (0, v),
+        Scale::LinearFloat(v) => (1, v),
+    };
+    splat
+        .position
+        .iter()
+        .map(bits)
+        .chain([splat.normal.is_some() as u32])
+        .chain(normal)
+        .chain(color)
+        .chain(opacity)
+        .chain([scale.0])
+        .chain(scale.1.iter().map(bits))
+        .chain(splat.rotation.coords.iter().map(bits))
+}
+
+/// Indices of splats that exactly duplicate an earlier one. Splats are bucketed by a hash of their canonical bits and
+/// compared in full only when hashes collide.
+fn find_duplicates(splats: &[UberSplat]) -> HashSet<usize> {
+    let mut first = HashMap::<u64, usize>::new();
+    // Distinct splats that share a hash with the splat in `first`, which should be rare.
+    let mut collisions = HashMap::<u64, Vec<usize>>::new();
+    let mut duplicates = HashSet::new();
+    let same = |a: usize, b: usize| canonical_bits(&splats[a]).eq(canonical_bits(&splats[b]));
+    for (index, splat) in splats.iter().enumerate() {
+        let mut hasher = DefaultHasher::new();
+        canonical_bits(splat).for_each(|bits| hasher.write_u32(bits));
+        let hash = hasher.finish();
+        let Some(&existing) = first.get(&hash) else {
+            first.insert(hash, index);
+            continue;
+        };
+        let others = collisions.entry(hash).or_default();
+        if same(existing, index) || others.iter().any(|other| same(*other, index)) {
+            duplicates.insert(index);
+        } else {
+            others.push(index);
+        }
+    }
+    duplicates
+}
+
+/// Fix what can be fixed and drop splats that can not be, returning the kept splats and a log of changes.
+///
+/// Rotations are normalised, which also repairs the denormalised quaternions produced by older `.splat` encoders.
+pub fn repair_splats(
+    splats: Vec<UberSplat>,
+    options: &RepairOptions,
+) -> (Vec<UberSplat>, RepairLog) {
+    let mut log = RepairLog::default();
+    let max_scale = options.max_scale.unwrap_or_else(|| {
+        let (min, max) = splats
+            .iter()
+            .filter(|s| s.position.iter().all(|v| v.is_finite()))
+            .fold(
+                (
+                    Vector3::repeat(f32::INFINITY),
+                    Vector3::repeat(f32::NEG_INFINITY),
+                ),
+                |(min, max), s| (min.inf(&s.position), max.sup(&s.position)),
+            );
+        let diagonal = (max - min).norm();
+        // A scene with no extent, such as a single splat, gives no size to compare scales against, and a floor of 1
+        // keeps small scenes from being flattened, as in the scale check of `validate`.
+        if diagonal.is_finite() && diagonal > 0.0 {
+            diagonal.max(1.0).max(options.min_scale)
+        } else {
+            f32::MAX
+        }
+    });
+
+    // Use the most common SH degree for splats with coefficient counts that match no degree.
+    let mut degrees = BTreeMap::new();
+    for splat in &splats {
+        if let Color::SphericalHarmonic(_, rest) = &splat.color {
+            if let Some(degree) = sh_degree_for_rest_len(rest.len()) {
+                *degrees.entry(degree).or_insert(0) += 1;
+            }
+        }
+    }
+    let common_degree = degrees
+        .iter()
+        .max_by_key(|(_, count)| **count)
+        .map_or(0, |(degree, _)| *degree);
+
+    let duplicates = if options.drop_duplicates {
+        find_duplicates(&splats)
+    } else {
+        HashSet::new()
+    };
+    let mut repaired = Vec::with_capacity(splats.len());
+    for (index, mut splat) in splats.into_iter().enumerate() {
+        if !splat.is_finite() {
+            log.record("dropped non-finite", index);
+            continue;
+        }
+        if duplicates.contains(&index) {
+            log.record("dropped duplicate", index);
+            continue;
+        }
+
+        let norm = splat.rotation.norm();
+        if norm < 1e-8 {
+            splat.rotation = Quaternion::identity();
+            log.record("reset zero rotation", index);
+        } else if (norm - 1.0).abs() > 1e-4 {
+            splat.rotation /= norm;
+            log.record("normalised rotation", index);
+        }
+
+        if let Some(scale) = clamp_scale(&splat.scale, options.min_scale, max_scale) {
+            splat.scale = scale;
+            log.record("clamped scale", index);
+        }
+
+        if let Opacity::LinearFloat(value) = splat.opacity {
+            if !(0.0..=1.0).contains(&value) {
+                splat.opacity = Opacity::LinearFloat(value.clamp(0.0, 1.0));
+                log.record("clamped opacity", index);
+            }
+        }
+
+        match &mut splat.color {
+            Color::LinearFloat(color) if color.iter().any(|v| !(0.0..=1.0).contains(v)) => {
+                *color = color.map(|v| v.clamp(0.0, 1.0));
+                log.record("clamped color", index);
+            }
+            Color::SphericalHarmonic(_, rest) if sh_degree_for_rest_len(rest.len()).is_none() => {
+                // Coefficient counts that match no degree can not be split into channels, so pad or truncate flat.
+                rest.resize(3 * (sh_coefficient_count(common_degree) - 1), 0.0);
+                log.record("resized spherical harmonics", index);
+            }
+            _ => (),
+        }
+
+        if let Some(normal) = splat.normal {
+            if normal.norm_squared() > 0.0 && (normal.norm() - 1.0).abs() > 1e-4 {
+                splat.normal = Some(normal.normalize());
+                log.record("normalised normal", index);
+            }
+        }
+        repaired.push(splat);
+    }
+    (repaired, log)
+}
+
+#[cfg(test)]
+#[test]
+fn test_repair_splats_fixes_and_drops() {
+    let mut splats = (0..4)
+        .map(|i| UberSplat {
+            rotation: Quaternion::new(0.5, 0.0, 0.0, 0.0),
+            ..UberSplat::test_splat(Vector3::new(i as f32, 0.0, 0.0))
+        })
+        .collect::<Vec<_>>();
+    splats[1].position.z = f32::INFINITY;
+    splats[2].opacity = Opacity::LinearFloat(-0.5);
+    splats[3].scale = Scale::Exponent(Vector3::new(-30.0, -2.0, -2.0));
+    splats.push(splats[0].clone());
+    // Negative zero duplicates positive zero.
+    splats.push(splats[0].clone());
+    splats[5].position.y = -0.0;
+
+    let options = RepairOptions {
+        min_scale: 1e-7,
+        max_scale: None,
+        drop_duplicates: true,
+    };
+    let (repaired, log) = repair_splats(splats, &options);
+    assert_eq!(repaired.len(), 3);
+    assert!(repaired
+        .iter()
+        .all(|s| (s.rotation.norm() - 1.0).abs() < 1e-6));
+    assert_eq!(repaired[1].opacity, Opacity::LinearFloat(0.0));
+    assert!((repaired[2].scale.to_linear_float().x - 1e-7).abs() < 1e-12);
+    let count = |category: &str| {
+        log.changes
+            .iter()
+            .find(|c| c.category == category)
+            .unwrap()
+            .count
+    };
+    assert_eq!(count("dropped non-finite"), 1);
+    assert_eq!(count("dropped duplicate"), 2);
+    assert_eq!(count("normalised rotation"), 3);
+    assert_eq!(count("clamped scale"), 1);
+
+    // A single splat has no extent, which does not clamp its scale.
+    let mut single = UberSplat::test_splat(Vector3::zeros());
+    single.scale = Scale::Exponent(Vector3::new(0.5, -0.1, 1.6));
+    let (repaired, log) = repair_splats(vec![single.clone()], &options);
+    assert_eq!(repaired[0].scale, single.scale);
+    assert!(log.changes.is_empty());
+}
@@ -0,0 +1,262 @@
+use crate::*;
+use anyhow::Result;
+use clap::ValueEnum;
+use nalgebra::Vector3;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum SortOrder {
+    /// Z-order curve over quantised positions
+    Morton,
+    /// Hilbert curve over quantised positions
+    Hilbert,
+    /// Position along an axis, nearest first
+    Depth,
+    /// Linear opacity, most opaque first
+    Opacity,
+    /// Volume of the gaussian, largest first
+    Size,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub fn index(&self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+}
+
+/// Bits per axis used when quantising positions; three axes fit in a `u64` curve index.
+const CURVE_BITS: u32 = 21;
+
+fn quantise_positions(splats: &[UberSplat]) -> Vec<[u32; 3]> {
+    let (min, max) = find_bounds(splats);
+    let extent = (max - min).map(|v| if v > 0.0 { v } else { 1.0 });
+    let cells = ((1_u32 << CURVE_BITS) - 1) as f32;
+    splats
+        .iter()
+        .map(|splat| {
+            let normalised = (splat.position - min).component_div(&extent);
+            let q = normalised.map(|v| (v.clamp(0.0, 1.0) * cells).round() as u32);
+            [q.x, q.y, q.z]
+        })
+        .collect()
+}
+
+/// Interleave the bits of three coordinates, most significant bit first, with `x` leading.
+fn interleave_bits(coordinates: [u32; 3], bits: u32) -> u64 {
+    let mut index = 0_u64;
+    for bit in (0..bits).rev() {
+        for coordinate in coordinates {
+            index = (index << 1) | ((coordinate >> bit) & 1) as u64;
+        }
+    }
+    index
+}
+
+pub fn morton_index(coordinates: [u32; 3], bits: u32) -> u64 {
+    interleave_bits(coordinates, bits)
+}
+
+/// Hilbert curve index using John Skilling's transpose algorithm ("Programming the Hilbert curve", 2004).
+pub fn hilbert_index(coordinates: [u32; 3], bits: u32) -> u64 {
+    let mut x = coordinates;
+    let m = 1_u32 << (bits - 1);
+
+    // Inverse undo excess work
+    let mut q = m;
+    while q > 1 {
+        let p = q - 1;
+        for i in 0..3 {
+            if x[i] & q != 0 {
+                x[0] ^= p;
+            } else {
+                let t = (x[0] ^ x[i]) & p;
+                x[0] ^= t;
+                x[i] ^= t;
+            }
+        }
+        q >>= 1;
+    }
+
+    // Gray encode
+    for i in 1..3 {
+        x[i] ^= x[i - 1];
+    }
+    let mut t = 0;
+    q = m;
+    while q > 1 {
+        if x[2] & q != 0 {
+            t ^= q - 1;
+        }
+        q >>= 1;
+    }
+    for value in &mut x {
+        *value ^= t;
+    }
+
+    interleave_bits(x, bits)
+}
+
+fn sort_by_key<K: Copy>(
+    splats: &mut Vec<UberSplat>,
+    keys: Vec<K>,
+    compare: impl Fn(&K, &K) -> Ordering,
+) {
+    let mut keyed = std::mem::take(splats)
+        .into_iter()
+        .zip(keys)
+        .collect::<Vec<_>>();
+    keyed.sort_by(|a, b| compare(&a.1, &b.1));
+    splats.extend(keyed.into_iter().map(|(splat, _)| splat));
+}
+
+/// Reorder splats. The sort is stable, so splats with equal keys keep their input order and output is reproducible.
+pub fn sort_splats(splats: &mut Vec<UberSplat>, order: SortOrder, axis: Axis, reverse: bool) {
+    let direction = |ordering: Ordering| {
+        if reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    };
+    match order {
+        SortOrder::Morton | SortOrder::Hilbert => {
+            let keys = quantise_positions(splats)
+                .into_iter()
+                .map(|q| match order {
+                    SortOrder::Morton => morton_index(q, CURVE_BITS),
+                    _ => hilbert_index(q, CURVE_BITS),
+                })
+                .collect();
+            sort_by_key(splats, keys, |a: &u64, b| direction(a.cmp(b)));
+        }
+        SortOrder::Depth => {
+            let keys = splats.iter().map(|s| s.position[axis.index()]).collect();
+            sort_by_key(splats, keys, |a: &f32, b| direction(a.total_cmp(b)));
+        }
+        SortOrder::Opacity => {
+            let keys = splats.iter().map(|s| s.opacity.to_linear_float()).collect();
+            sort_by_key(splats, keys, |a: &f32, b| direction(b.total_cmp(a)));
+        }
+        SortOrder::Size => {
+            let keys = splats
+                .iter()
+                .map(|s| s.scale.to_linear_float().iter().product::<f32>())
+                .collect();
+            sort_by_key(splats, keys, |a: &f32, b| direction(b.total_cmp(a)));
+        }
+    }
+}
+
+/// Depth of each splat along the view direction of a camera at `eye` looking at `target`.
+pub fn view_depths(splats: &[UberSplat], eye: &Vector3<f32>, target: &Vector3<f32>) -> Vec<f32> {
+    let forward = (target - eye).normalize();
+    splats
+        .iter()
+        .map(|splat| (splat.position - eye).dot(&forward))
+        .collect()
+}
+
+/// Sort splats back to front for a set of views.
+///
+/// With one view this is a plain view-space depth sort. With several, each splat's rank in every view's
+/// back-to-front order is computed and splats are ordered by their median rank.
+pub fn sort_for_views(
+    splats: &mut Vec<UberSplat>,
+    views: &[(Vector3<f32>, Vector3<f32>)],
+) -> Result<()> {
+    if views.is_empty() {
+        anyhow::bail!("At least one view is required");
+    }
+    let mut ranks = vec![Vec::with_capacity(views.len()); splats.len()];
+    for (eye, target) in views {
+        if (target - eye).norm() == 0.0 {
+            anyhow::bail!("Eye and target must differ");
+        }
+        let depths = view_depths(splats, eye, target);
+        let mut order = (0..splats.len()).collect::<Vec<usize>>();
+        order.sort_by(|a, b| depths[*b].total_cmp(&depths[*a]));
+        for (rank, index) in order.into_iter().enumerate() {
+            ranks[index].push(rank as f32);
+        }
+    }
+    let medians = ranks
+        .into_iter()
+        .map(|mut ranks| {
+            ranks.sort_by(f32::total_cmp);
+            let middle = ranks.len() / 2;
+            if ranks.len() % 2 == 0 {
+                (ranks[middle - 1] + ranks[middle]) / 2.0
+            } else {
+                ranks[middle]
+            }
+        })
+        .collect();
+    sort_by_key(splats, medians, |a: &f32, b| a.total_cmp(b));
+    Ok(())
+}
+
+#[cfg(test)]
+#[test]
+fn test_hilbert_index_visits_adjacent_cells() {
+    let bits = 3;
+    let size = 1 << bits;
+    let mut cells = Vec::new();
+    for x in 0..size {
+        for y in 0..size {
+            for z in 0..size {
+                cells.push(([x, y, z], hilbert_index([x, y, z], bits)));
+            }
+        }
+    }
+    cells.sort_by_key(|(_, index)| *index);
+    for (i, (_, index)) in cells.iter().enumerate() {
+        assert_eq!(*index, i as u64);
+    }
+    for pair in cells.windows(2) {
+        let (a, b) = (pair[0].0, pair[1].0);
+        let distance: u32 = (0..3).map(|i| a[i].abs_diff(b[i])).sum();
+        assert_eq!(distance, 1);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_sort_for_views_uses_median_rank() {
+    let splat = |x: f32, y: f32, z: f32| UberSplat::test_splat(Vector3::new(x, y, z));
+    // The last splat is behind the first camera, so it is drawn last there, and ties with the first splat in depth
+    // for the other two cameras, where the stable sort keeps the first splat behind it.
+    let mut splats = vec![
+        splat(0.0, 0.0, 0.0),
+        splat(1.0, 1.0, 1.0),
+        splat(-1.0, -1.0, -1.0),
+        splat(0.0, 0.0, 20.0),
+    ];
+    let views = [
+        (Vector3::new(0.0, 0.0, 10.0), Vector3::zeros()),
+        (Vector3::new(10.0, 0.0, 0.0), Vector3::zeros()),
+        (Vector3::new(0.0, 10.0, 0.0), Vector3::zeros()),
+    ];
+    // Back to front ranks are [1, 1, 1], [2, 3, 3], [0, 0, 0] and [3, 2, 2].
+    sort_for_views(&mut splats, &views).unwrap();
+    let order = splats.iter().map(|s| s.position).collect::<Vec<_>>();
+    assert_eq!(
+        order,
+        [
+            Vector3::new(-1.0, -1.0, -1.0),
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 20.0),
+            Vector3::new(1.0, 1.0, 1.0),
+        ]
+    );
+}
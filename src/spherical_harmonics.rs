@@ -0,0 +1,287 @@
+use nalgebra::{DMatrix, UnitQuaternion, Vector3};
+
+// Real spherical harmonic constants, using the same sign conventions as the reference 3DGS implementation.
+pub const SH_C0: f32 = 0.282_094_8;
+const SH_C1: f32 = 0.488_602_52;
+const SH_C2: [f32; 5] = [
+    1.092_548_4,
+    -1.092_548_4,
+    0.315_391_57,
+    -1.092_548_4,
+    0.546_274_2,
+];
+const SH_C3: [f32; 7] = [
+    -0.590_043_6,
+    2.890_611_4,
+    -0.457_045_8,
+    0.373_176_33,
+    -0.457_045_8,
+    1.445_305_7,
+    -0.590_043_6,
+];
+
+pub const MAX_SH_DEGREE: usize = 3;
+
+/// Number of coefficients per color channel for a given degree (including the DC term).
+pub fn sh_coefficient_count(degree: usize) -> usize {
+    (degree + 1) * (degree + 1)
+}
+
+/// The degree implied by the length of an `f_rest` style array (three channels, DC term excluded).
+pub fn sh_degree_for_rest_len(len: usize) -> Option<usize> {
+    (0..=MAX_SH_DEGREE).find(|degree| 3 * (sh_coefficient_count(*degree) - 1) == len)
+}
+
+/// Evaluate the real SH basis functions up to `degree` for a unit direction.
+pub fn sh_basis(direction: &Vector3<f32>, degree: usize) -> Vec<f32> {
+    let (x, y, z) = (direction.x, direction.y, direction.z);
+    let mut basis = vec![SH_C0];
+    if degree >= 1 {
+        basis.extend([-SH_C1 * y, SH_C1 * z, -SH_C1 * x]);
+    }
+    if degree >= 2 {
+        let (xx, yy, zz) = (x * x, y * y, z * z);
+        basis.extend([
+            SH_C2[0] * x * y,
+            SH_C2[1] * y * z,
+            SH_C2[2] * (2.0 * zz - xx - yy),
+            SH_C2[3] * x * z,
+            SH_C2[4] * (xx - yy),
+        ]);
+    }
+    if degree >= 3 {
+        let (xx, yy, zz) = (x * x, y * y, z * z);
+        basis.extend([
+            SH_C3[0] * y * (3.0 * xx - yy),
+            SH_C3[1] * x * y * z,
+            SH_C3[2] * y * (4.0 * zz - xx - yy),
+            SH_C3[3] * z * (2.0 * zz - 3.0 * xx - 3.0 * yy),
+            SH_C3[4] * x * (4.0 * zz - xx - yy),
+            SH_C3[5] * z * (xx - yy),
+            SH_C3[6] * x * (xx - 3.0 * yy),
+        ]);
+    }
+    basis
+}
+
+/// Evaluate a spherical harmonic color (DC term plus channel-major `f_rest`) for a unit view direction.
+///
+/// As in the reference implementation, the result is offset by 0.5 and clamped at zero.
+pub fn evaluate_sh(dc: &Vector3<f32>, rest: &[f32], direction: &Vector3<f32>) -> Vector3<f32> {
+    let count = rest.len() / 3;
+    let degree = sh_degree_for_rest_len(rest.len()).unwrap_or(0);
+    let basis = sh_basis(direction, degree);
+    let mut color = dc * SH_C0;
+    for channel in 0..3 {
+        for k in 1..basis.len() {
+            color[channel] += basis[k] * rest[channel * count + k - 1];
+        }
+    }
+    color.map(|v| (v + 0.5).max(0.0))
+}
+
+/// Truncate or zero-pad an `f_rest` array (channel-major, as stored in `.ply` files) to a different degree.
+pub fn resize_sh_rest(rest: &[f32], to_degree: usize) -> Vec<f32> {
+    let from_count = rest.len() / 3;
+    let to_count = sh_coefficient_count(to_degree) - 1;
+    let mut resized = vec![0.0; 3 * to_count];
+    for channel in 0..3 {
+        for k in 0..from_count.min(to_count) {
+            resized[channel * to_count + k] = rest[channel * from_count + k];
+        }
+    }
+    resized
+}
+
+/// Evenly distributed unit directions on the sphere.
+pub fn fibonacci_sphere(count: usize) -> Vec<Vector3<f32>> {
+    let golden_angle = std::f32::consts::PI * (3.0 - 5.0_f32.sqrt());
+    (0..count)
+        .map(|i| {
+            let y = 1.0 - 2.0 * (i as f32 + 0.5) / count as f32;
+            let radius = (1.0 - y * y).sqrt();
+            let theta = golden_angle * i as f32;
+            Vector3::new(radius * theta.cos(), y, radius * theta.sin())
+        })
+        .collect()
+}
+
+/// Per-band matrices mapping SH coefficients to those of the same function rotated by `rotation`.
+///
+/// Each band is closed under rotation, so the matrices are found exactly by a least squares fit over sampled directions.
+pub fn sh_rotation_matrices(rotation: &UnitQuaternion<f32>, degree: usize) -> Vec<DMatrix<f32>> {
+    let directions = fibonacci_sphere(64);
+    let inverse = rotation.inverse();
+    (1..=degree)
+        .map(|band| {
+            let start = band * band;
+            let size = 2 * band + 1;
+            let basis = DMatrix::<f64>::from_fn(directions.len(), size, |row, column| {
+                sh_basis(&directions[row], band)[start + column] as f64
+            });
+            let rotated = DMatrix::<f64>::from_fn(directions.len(), size, |row, column| {
+                sh_basis(&(inverse * directions[row]), band)[start + column] as f64
+            });
+            let pseudo_inverse = basis.pseudo_inverse(1e-9).unwrap();
+            (pseudo_inverse * rotated).cast::<f32>()
+        })
+        .collect()
+}
+
+/// Rotate an `f_rest` array using matrices from `sh_rotation_matrices`.
+pub fn rotate_sh_rest(rest: &[f32], matrices: &[DMatrix<f32>]) -> Vec<f32> {
+    let count = rest.len() / 3;
+    let mut rotated = rest.to_vec();
+    for channel in 0..3 {
+        for (index, matrix) in matrices.iter().enumerate() {
+            let band = index + 1;
+            let start = band * band - 1;
+            let size = 2 * band + 1;
+            if start + size > count {
+                break;
+            }
+            let offset = channel * count + start;
+            let coefficients = DMatrix::from_column_slice(size, 1, &rest[offset..offset + size]);
+            let result = matrix * coefficients;
+            rotated[offset..offset + size].copy_from_slice(result.as_slice());
+        }
+    }
+    rotated
+}
+
+/// Fits lower degree spherical harmonics to higher degree ones over sampled view directions.
+///
+/// Plain truncation is already the least squares fit of the raw function over the whole sphere, so the fit is made to
+/// the displayed color instead, which is clamped at zero. Directions that are black both before and after are left
+/// out, so the lower degree can spend its coefficients on the directions that are visible.
+pub struct ShBaker {
+    degree: usize,
+    /// Basis functions up to `MAX_SH_DEGREE` for each sampled direction.
+    basis: Vec<Vec<f32>>,
+}
+
+impl ShBaker {
+    pub fn new(degree: usize, samples: usize) -> ShBaker {
+        let basis = fibonacci_sphere(samples)
+            .iter()
+            .map(|direction| sh_basis(direction, MAX_SH_DEGREE))
+            .collect();
+        ShBaker { degree, basis }
+    }
+
+    /// The DC term and channel-major `f_rest` of degree `self.degree` best matching `dc` and `rest`.
+    pub fn bake(&self, dc: &Vector3<f32>, rest: &[f32]) -> (Vector3<f32>, Vec<f32>) {
+        let from_degree = sh_degree_for_rest_len(rest.len()).unwrap_or(0);
+        if from_degree <= self.degree {
+            return (*dc, resize_sh_rest(rest, self.degree));
+        }
+        let from_count = sh_coefficient_count(from_degree);
+        let to_count = sh_coefficient_count(self.degree);
+        let mut baked_dc = *dc;
+        let mut baked_rest = resize_sh_rest(rest, self.degree);
+        for channel in 0..3 {
+            let coefficient = |rest: &[f32], count: usize, k: usize| {
+                if k == 0 {
+                    dc[channel]
+                } else {
+                    rest[channel * (count - 1) + k - 1]
+                }
+            };
+            // Displayed values, less the 0.5 offset, which the fit does not need to reproduce.
+            let targets = self
+                .basis
+                .iter()
+                .map(|basis| {
+                    let value = (0..from_count)
+                        .map(|k| basis[k] * coefficient(rest, from_count, k))
+                        .sum::<f32>();
+                    (value + 0.5).max(0.0) as f64 - 0.5
+                })
+                .collect::<Vec<f64>>();
+            let mut included = vec![true; self.basis.len()];
+            let mut solution = None;
+            for _ in 0..4 {
+                let mut normal = DMatrix::<f64>::zeros(to_count, to_count);
+                let mut right = DMatrix::<f64>::zeros(to_count, 1);
+                for (basis, target) in
+                    self.basis.iter().zip(&targets).zip(&included).filter_map(
+                        |((basis, target), included)| included.then_some((basis, target)),
+                    )
+                {
+                    for row in 0..to_count {
+                        right[row] += basis[row] as f64 * target;
+                        for column in 0..to_count {
+                            normal[(row, column)] += (basis[row] * basis[column]) as f64;
+                        }
+                    }
+                }
+                let Some(cholesky) = normal.cholesky() else {
+                    break;
+                };
+                let fit = cholesky.solve(&right);
+                let next = self
+                    .basis
+                    .iter()
+                    .zip(&targets)
+                    .map(|(basis, target)| {
+                        let value = (0..to_count).map(|k| basis[k] as f64 * fit[k]).sum::<f64>();
+                        !(*target <= -0.5 && value <= -0.5)
+                    })
+                    .collect::<Vec<bool>>();
+                solution = Some(fit);
+                if next == included {
+                    break;
+                }
+                included = next;
+            }
+            // Without enough visible directions to fit, truncation is kept.
+            if let Some(fit) = solution {
+                baked_dc[channel] = fit[0] as f32;
+                for k in 1..to_count {
+                    baked_rest[channel * (to_count - 1) + k - 1] = fit[k] as f32;
+                }
+            }
+        }
+        (baked_dc, baked_rest)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_rotate_sh_rest_matches_rotated_evaluation() {
+    let rotation = UnitQuaternion::from_euler_angles(0.3, -1.1, 2.0);
+    let rest = (0..45)
+        .map(|i| ((i * 7 % 11) as f32 - 5.0) / 10.0)
+        .collect::<Vec<f32>>();
+    let rotated = rotate_sh_rest(&rest, &sh_rotation_matrices(&rotation, 3));
+    let evaluate = |rest: &[f32], direction: &Vector3<f32>| {
+        let basis = sh_basis(direction, 3);
+        (1..16).map(|k| basis[k] * rest[k - 1]).sum::<f32>()
+    };
+    for direction in fibonacci_sphere(10) {
+        let expected = evaluate(&rest, &(rotation.inverse() * direction));
+        let actual = evaluate(&rotated, &direction);
+        assert!((expected - actual).abs() < 1e-4);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_bake_beats_truncation_on_clamped_colors() {
+    // A dark color with strong higher bands, so that it is clamped to black in many directions.
+    let dc = Vector3::repeat(-1.2);
+    let rest = (0..45)
+        .map(|i| ((i * 5 % 13) as f32 - 6.0) / 8.0)
+        .collect::<Vec<f32>>();
+    let baked = ShBaker::new(1, 256).bake(&dc, &rest);
+    let truncated = (dc, resize_sh_rest(&rest, 1));
+    assert_eq!(baked.1.len(), 9);
+    // Squared error of the displayed color, which is what the fit minimises.
+    let error = |(fit_dc, fit_rest): &(Vector3<f32>, Vec<f32>)| {
+        fibonacci_sphere(500)
+            .iter()
+            .map(|d| (evaluate_sh(fit_dc, fit_rest, d) - evaluate_sh(&dc, &rest, d)).norm_squared())
+            .sum::<f32>()
+    };
+    assert!(error(&baked) < 0.9 * error(&truncated));
+}
@@ -0,0 +1,149 @@
+use crate::*;
+use anyhow::Result;
+use nalgebra::{Matrix3, Rotation3, UnitQuaternion, Vector3};
+use ply_rs::parser::Parser;
+use ply_rs::ply::DefaultElement;
+use std::path::Path;
+
+/// Linear scale given to the flat axis of surfels, which have no thickness.
+pub const SURFEL_THICKNESS: f32 = 1e-7;
+
+/// The `.ply` files written by 2D Gaussian Splatting <https://surfsplatting.github.io/>: flat surfels with the 3DGS
+/// properties, but only `scale_0` and `scale_1`. The surfel's normal is the third axis of its rotation.
+pub struct Splat2d;
+
+/// The normal of a surfel, the third axis of its rotation.
+fn surfel_normal(rotation: &UnitQuaternion<f32>) -> Vector3<f32> {
+    rotation * Vector3::z()
+}
+
+/// Flatten a splat along its smallest axis into a surfel, reordering its axes so the flat one is the third, and set
+/// its normal to that axis.
+pub fn flatten_to_surfel(splat: &UberSplat) -> UberSplat {
+    let rotation = UnitQuaternion::from_quaternion(splat.rotation);
+    let matrix = rotation.to_rotation_matrix().into_inner();
+    let scale = splat.scale.to_linear_float();
+    let thin = scale.imin();
+    // A cyclic order keeps the axes right handed.
+    let order = [(thin + 1) % 3, (thin + 2) % 3, thin];
+    let axes = Matrix3::from_columns(&order.map(|i| matrix.column(i).into_owned()));
+    let rotation = UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(axes));
+    let flattened = Vector3::new(scale[order[0]], scale[order[1]], SURFEL_THICKNESS);
+    UberSplat {
+        normal: Some(surfel_normal(&rotation)),
+        scale: splat.scale.with_linear(flattened),
+        rotation: rotation.into_inner(),
+        ..splat.clone()
+    }
+}
+
+impl SplatFormat for Splat2d {
+    fn is_format(path: &Path) -> FormatResult {
+        if inner_path(path).extension().unwrap_or_default() != "ply" {
+            return FormatResult::No("Not a PLY file".to_string());
+        }
+        if !path.exists() {
+            return FormatResult::No(
+                "2DGS files are only written by `convert --surfels`".to_string(),
+            );
+        }
+        let header = open_input(path)
+            .ok()
+            .and_then(|mut f| Parser::<DefaultElement>::new().read_header(&mut f).ok());
+        let Some(vertex) = header.as_ref().and_then(|h| h.elements.get("vertex")) else {
+            return FormatResult::No("No vertex element".to_string());
+        };
+        let has = |name: &str| vertex.properties.contains_key(name);
+        if has("scale_0") && has("scale_1") && !has("scale_2") && has("rot_3") {
+            FormatResult::Yes
+        } else {
+            FormatResult::No("Not a 2DGS file".to_string())
+        }
+    }
+
+    fn load(path: &Path) -> Result<Vec<UberSplat>> {
+        let splats = SplatA::read_ply(path)?
+            .iter()
+            .map(|splat| {
+                let mut splat = UberSplat::from(splat);
+                let mut scale = splat.scale.to_linear_float();
+                scale.z = SURFEL_THICKNESS;
+                splat.scale = splat.scale.with_linear(scale);
+                // 2DGS writes zero normals, so derive them from the rotation.
+                let rotation = UnitQuaternion::from_quaternion(splat.rotation);
+                splat.normal = Some(surfel_normal(&rotation));
+                splat
+            })
+            .collect();
+        Ok(splats)
+    }
+
+    fn save(splats: &[UberSplat], path: &Path) -> Result<()> {
+        let degree = splats
+            .iter()
+            .filter_map(|splat| splat.color.sh_degree())
+            .max()
+            .unwrap_or(MAX_SH_DEGREE);
+        let splats = splats
+            .iter()
+            .map(|splat| SplatA::from(&flatten_to_surfel(splat)))
+            .collect::<Vec<_>>();
+        SplatA::write_ply(&splats, degree, &["scale_2"], path)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_surfel_round_trip() {
+    let splat = UberSplat::new(
+        Vector3::new(1.0, 2.0, 3.0),
+        None,
+        Color::FirstOrderSphericalHarmonic(Vector3::new(0.1, 0.2, 0.3)),
+        Opacity::LogitFloat(1.0),
+        Scale::Exponent(Vector3::new(0.2_f32, 0.01, 0.4).map(f32::ln)),
+        nalgebra::Quaternion::new(0.8, 0.3, -0.2, 0.1),
+    );
+    let rotation = UnitQuaternion::from_quaternion(splat.rotation);
+    let flat = flatten_to_surfel(&splat);
+    // The normal is the splat's thinnest axis, and the surfel spans the other two.
+    let normal = flat.normal.unwrap();
+    assert!((normal.dot(&(rotation * Vector3::y())).abs() - 1.0).abs() < 1e-5);
+    let scale = flat.scale.to_linear_float();
+    assert!((scale - Vector3::new(0.4, 0.2, SURFEL_THICKNESS)).norm() < 1e-6);
+
+    let path = std::env::temp_dir().join(format!("surfels-{}.ply", std::process::id()));
+    Splat2d::save(&[splat], &path).unwrap();
+    assert_eq!(Splat2d::is_format(&path), FormatResult::Yes);
+    assert!(matches!(guess_format(&path), Some(SplatFormats::Splat2d)));
+    let loaded = Splat2d::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!((loaded[0].normal.unwrap() - normal).norm() < 1e-5);
+    assert!((loaded[0].scale.to_linear_float() - scale).norm() < 1e-5);
+    assert_eq!(loaded[0].position, flat.position);
+}
+
+#[cfg(test)]
+#[test]
+fn test_load_2dgs_vertex() {
+    // A surfel turned 90 degrees about x, with the real part of its quaternion in rot_0 as 2DGS writes it.
+    let half = std::f32::consts::FRAC_1_SQRT_2;
+    let ply = format!(
+        "ply\nformat ascii 1.0\nelement vertex 1\n{}end_header\n0 0 0 0 0 0 0 0 0 0 -1 -2 {} {} 0 0\n",
+        [
+            "x", "y", "z", "nx", "ny", "nz", "f_dc_0", "f_dc_1", "f_dc_2", "opacity", "scale_0",
+            "scale_1", "rot_0", "rot_1", "rot_2", "rot_3",
+        ]
+        .map(|name| format!("property float {}\n", name))
+        .concat(),
+        half,
+        half
+    );
+    let path = std::env::temp_dir().join(format!("2dgs-{}.ply", std::process::id()));
+    std::fs::write(&path, ply).unwrap();
+    let loaded = Splat2d::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!((loaded[0].normal.unwrap() - Vector3::new(0.0, -1.0, 0.0)).norm() < 1e-5);
+    let scale = loaded[0].scale.to_linear_float();
+    let expected = Vector3::new((-1.0_f32).exp(), (-2.0_f32).exp(), SURFEL_THICKNESS);
+    assert!((scale - expected).norm() < 1e-6);
+}
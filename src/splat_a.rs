@@ -14,20 +14,26 @@ pub struct SplatA {
     pub position: Vector3<f32>,       // 3 elements
     pub normal: Option<Vector3<f32>>, // 3 elements (optional)
     pub f_dc: Vector3<f32>,           // 3 elements
-    pub f_rest: Option<Vec<f32>>,     // 45 elements (optional)
+    pub f_rest: Option<Vec<f32>>,     // 9, 24 or 45 elements (optional)
     pub opacity: f32,                 // 1 element
     pub scale: Vector3<f32>,          // 3 elements
-    pub rot: Vector4<f32>,            // 4 elements
+    pub rot: Vector4<f32>,            // 4 elements, rot_0..rot_3 with the real part first
 }
 
 impl SplatFormat for SplatA {
     fn is_format(path: &Path) -> FormatResult {
-        if path.extension().unwrap() == "ply" {
+        if inner_path(path).extension().unwrap_or_default() == "ply" {
+            if !path.exists() {
+                return FormatResult::Maybe(Some(0.666));
+            }
             // TODO: too simple
-            let f = std::fs::File::open(path).unwrap();
-            let mut f = std::io::BufReader::new(f);
+            let Ok(mut f) = open_input(path) else {
+                return FormatResult::No("Can not open file".to_string());
+            };
             let splat_parser = parser::Parser::<SplatA>::new();
-            let header = splat_parser.read_header(&mut f).unwrap();
+            let Ok(header) = splat_parser.read_header(&mut f) else {
+                return FormatResult::No("Can not read PLY header".to_string());
+            };
             if SplatA::is_splat_a(&header) {
                 FormatResult::Yes
             } else {
@@ -38,10 +44,27 @@ impl SplatFormat for SplatA {
         }
     }
     fn load(path: &Path) -> Result<Vec<UberSplat>> {
-        let f = std::fs::File::open(path).unwrap();
-        let mut f = std::io::BufReader::new(f);
+        let splats = SplatA::read_ply(path)?;
+        Ok(splats.iter().map(UberSplat::from).collect())
+    }
+    fn save(splats: &[UberSplat], path: &Path) -> Result<()> {
+        // Scenes without spherical harmonics are written with every band, as the original 3DGS code expects.
+        let degree = splats
+            .iter()
+            .filter_map(|splat| splat.color.sh_degree())
+            .max()
+            .unwrap_or(MAX_SH_DEGREE);
+        let splats = splats.iter().map(SplatA::from).collect::<Vec<_>>();
+        SplatA::write_ply(&splats, degree, &[], path)
+    }
+}
+
+impl SplatA {
+    /// Read the vertices of a `.ply` with 3DGS property names.
+    pub fn read_ply(path: &Path) -> Result<Vec<SplatA>> {
+        let mut f = open_input(path)?;
         let splat_parser = parser::Parser::<SplatA>::new();
-        let header = splat_parser.read_header(&mut f).unwrap();
+        let header = splat_parser.read_header(&mut f)?;
 
         // Depending on the header, read the data into our structs..
         let mut splat_list = Vec::new();
@@ -49,30 +72,112 @@ impl SplatFormat for SplatA {
             // we could also just parse them in sequence, but the file format might change
             match element.name.as_ref() {
                 "vertex" => {
-                    splat_list = splat_parser
-                        .read_payload_for_element(&mut f, element, &header)
-                        .unwrap();
+                    splat_list = splat_parser.read_payload_for_element(&mut f, element, &header)?;
                 }
-                _ => panic!("Enexpeced element!"),
+                name => anyhow::bail!("Unexpected element {}", name),
             }
         }
 
-        let splats: Vec<UberSplat> = splat_list.iter().map(|splat| splat.into()).collect();
-        Ok(splats)
+        Ok(splat_list)
     }
-    fn save(_: &[UberSplat], _: &Path) -> Result<()> {
-        panic!("Not implemented");
+    /// Write a binary `.ply` with the properties of `property_names(degree)` except those in `excluded`.
+    pub fn write_ply(
+        splats: &[SplatA],
+        degree: usize,
+        excluded: &[&str],
+        path: &Path,
+    ) -> Result<()> {
+        let names = SplatA::property_names(degree)
+            .into_iter()
+            .filter(|name| !excluded.contains(&name.as_str()))
+            .collect::<Vec<_>>();
+        let mut element = ElementDef::new("vertex".to_string());
+        for name in &names {
+            element.properties.add(PropertyDef::new(
+                name.clone(),
+                PropertyType::Scalar(ScalarType::Float),
+            ));
+        }
+        let mut ply = Ply::<DefaultElement>::new();
+        ply.header.encoding = Encoding::BinaryLittleEndian;
+        ply.header.elements.add(element);
+        let vertices = splats
+            .iter()
+            .map(|splat| {
+                let mut element = splat.to_element(degree);
+                for name in excluded {
+                    element.remove(*name);
+                }
+                element
+            })
+            .collect();
+        ply.payload.insert("vertex".to_string(), vertices);
+        ply.make_consistent()?;
+
+        let mut f = create_output(path)?;
+        let writer = ply_rs::writer::Writer::new();
+        writer.write_ply(&mut f, &mut ply)?;
+        f.finish()?;
+        Ok(())
+    }
+
+    /// Property names in the order used by the original 3DGS `.ply` files, with `f_rest` for SH bands up to `degree`.
+    pub fn property_names(degree: usize) -> Vec<String> {
+        let mut names = vec![
+            "x", "y", "z", "nx", "ny", "nz", "f_dc_0", "f_dc_1", "f_dc_2",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<String>>();
+        let rest_len = 3 * (sh_coefficient_count(degree) - 1);
+        names.extend((0..rest_len).map(|i| format!("f_rest_{}", i)));
+        names.extend(
+            [
+                "opacity", "scale_0", "scale_1", "scale_2", "rot_0", "rot_1", "rot_2", "rot_3",
+            ]
+            .into_iter()
+            .map(String::from),
+        );
+        names
+    }
+
+    pub fn to_element(&self, degree: usize) -> DefaultElement {
+        let normal = self.normal.unwrap_or(Vector3::zeros());
+        let f_rest = resize_sh_rest(self.f_rest.as_deref().unwrap_or_default(), degree);
+        let mut values = vec![
+            self.position.x,
+            self.position.y,
+            self.position.z,
+            normal.x,
+            normal.y,
+            normal.z,
+            self.f_dc.x,
+            self.f_dc.y,
+            self.f_dc.z,
+        ];
+        values.extend(f_rest);
+        values.extend([
+            self.opacity,
+            self.scale.x,
+            self.scale.y,
+            self.scale.z,
+            self.rot.x,
+            self.rot.y,
+            self.rot.z,
+            self.rot.w,
+        ]);
+        let mut element = DefaultElement::new();
+        for (name, value) in SplatA::property_names(degree).into_iter().zip(values) {
+            element.insert(name, Property::Float(value));
+        }
+        element
     }
-}
 
-impl SplatA {
     pub fn is_splat_a(header: &Header) -> bool {
         if header.elements.len() != 1 {
-            println!("header.elements.len() != 1");
             return false;
         }
         let Some(element) = header.elements.get("vertex") else {
-            println!("header.elements.get(\"vertex\")");
             return false;
         };
         let required_properties = vec![
@@ -81,11 +186,9 @@ impl SplatA {
         ];
         for name in required_properties {
             let Some(property) = element.properties.get(name) else {
-                println!("element.properties.get(\"{}\")", name);
                 return false;
             };
             if property.data_type != PropertyType::Scalar(ScalarType::Float) {
-                println!("property.data_type != PropertyType::Scalar(ScalarType::Float)");
                 return false;
             }
         }
@@ -96,20 +199,21 @@ impl SplatA {
                 && property.unwrap().data_type == PropertyType::Scalar(ScalarType::Float)
         });
         if !has_normals {
-            println!("!has_normals");
             return false;
         }
-        let higher_order_spherical_harmonics_names = (0..45)
-            .map(|i| format!("f_rest_{}", i))
-            .collect::<Vec<String>>();
-        let has_higher_order_spherical_harmonics =
-            higher_order_spherical_harmonics_names.iter().all(|name| {
-                let property = element.properties.get(name);
+        let rest_len = element
+            .properties
+            .keys()
+            .filter(|name| name.starts_with("f_rest_"))
+            .count();
+        let has_spherical_harmonics = sh_degree_for_rest_len(rest_len).is_some()
+            && (0..rest_len).all(|i| {
+                let property = element.properties.get(&format!("f_rest_{}", i));
                 property.is_some()
                     && property.unwrap().data_type == PropertyType::Scalar(ScalarType::Float)
             });
-        if !has_higher_order_spherical_harmonics {
-            panic!("!has_higher_order_spherical_harmonics");
+        if !has_spherical_harmonics {
+            return false;
         }
         true
     }
@@ -171,20 +275,15 @@ impl ply_rs::ply::PropertyAccess for SplatA {
                 self.normal = Some(update_vector(self.normal, 2, v))
             }
             (name, ply_rs::ply::Property::Float(v)) => {
-                if name.starts_with("f_rest_") {
-                    let Some(index) = name.strip_prefix("f_rest_") else {
-                        panic!("Vertex: f_rest index out of bounds: {}", name);
-                    };
-                    let index = index.parse::<usize>().unwrap();
-                    if index >= 45 {
-                        panic!("Vertex: f_rest index out of bounds: {}", index);
-                    }
+                let index = name
+                    .strip_prefix("f_rest_")
+                    .and_then(|i| i.parse::<usize>().ok());
+                if let Some(index) = index.filter(|index| *index < 45) {
                     self.f_rest = Some(update_array(self.f_rest.clone(), index, v));
                 }
             }
-
-            // (_, _) => println!("Vertex: Unexpected key/value combination: key: {}", key),
-            (k, _) => panic!("Vertex: Unexpected key/value combination: key: {}", k),
+            // Other properties, such as the colors some tools add, are not part of a splat and are ignored.
+            _ => (),
         }
     }
 }
@@ -194,10 +293,13 @@ impl ply_rs::ply::PropertyAccess for SplatA {
 impl From<&SplatA> for UberSplat {
     fn from(splat: &SplatA) -> Self {
         // TODO: FIXME this is all totally invented.
-        let color = Color::SphericalHarmonic(splat.f_dc, splat.f_rest.clone().unwrap());
+        let color = match &splat.f_rest {
+            Some(rest) => Color::SphericalHarmonic(splat.f_dc, rest.clone()),
+            None => Color::FirstOrderSphericalHarmonic(splat.f_dc),
+        };
         let opacity = Opacity::LogitFloat(splat.opacity);
         let scale = Scale::Exponent(splat.scale);
-        let rotation = Quaternion::new(splat.rot.w, splat.rot.x, splat.rot.y, splat.rot.z);
+        let rotation = Quaternion::new(splat.rot.x, splat.rot.y, splat.rot.z, splat.rot.w);
         UberSplat::new(
             splat.position,
             splat.normal,
@@ -208,3 +310,35 @@ impl From<&SplatA> for UberSplat {
         )
     }
 }
+
+impl From<&UberSplat> for SplatA {
+    fn from(splat: &UberSplat) -> Self {
+        let (f_dc, f_rest) = match &splat.color {
+            Color::SphericalHarmonic(dc, rest) => (*dc, Some(rest.clone())),
+            Color::FirstOrderSphericalHarmonic(dc) => (*dc, None),
+            color => (color.to_linear_float().map(|v| (v - 0.5) / SH_C0), None),
+        };
+        let opacity = match splat.opacity {
+            Opacity::LogitFloat(value) => value,
+            ref opacity => {
+                let value = opacity.to_linear_float().clamp(1e-6, 1.0 - 1e-6);
+                (value / (1.0 - value)).ln()
+            }
+        };
+        let scale = match splat.scale {
+            Scale::Exponent(value) => value,
+            Scale::LinearFloat(value) => value.map(|v| v.max(f32::MIN_POSITIVE).ln()),
+        };
+        let rotation = splat.rotation;
+        let rot = Vector4::new(rotation.w, rotation.i, rotation.j, rotation.k);
+        SplatA::new(
+            splat.position,
+            splat.normal,
+            f_dc,
+            f_rest,
+            opacity,
+            scale,
+            rot,
+        )
+    }
+}
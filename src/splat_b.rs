@@ -3,7 +3,6 @@ use anyhow::Result;
 use bytemuck::{Pod, Zeroable};
 use derive_new::new as New;
 use nalgebra::{Quaternion, Vector3, Vector4};
-use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 
@@ -25,17 +24,42 @@ fn test_splat_b_size() {
     assert_eq!(std::mem::size_of::<SplatB>(), 32);
 }
 
+#[cfg(test)]
+#[test]
+fn test_ply_to_splat_matches_viewer_conversion() {
+    // Color and rotation bytes from the PLY conversion in antimatter15's viewer, which rounds to the nearest byte.
+    let expected: [([u8; 4], [u8; 4]); 3] = [
+        ([207, 182, 132, 179], [253, 104, 115, 126]),
+        ([108, 127, 116, 10], [255, 140, 123, 140]),
+        ([128, 159, 101, 35], [223, 94, 50, 121]),
+    ];
+    let input =
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("TestData/test-splat.3-points-from-train.ply");
+    let output = std::env::temp_dir().join(format!("viewer-{}.splat", std::process::id()));
+    SplatB::save(&SplatA::load(&input).unwrap(), &output).unwrap();
+    let data = std::fs::read(&output).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    assert_eq!(data.len(), 32 * expected.len());
+    for (record, (color, rotation)) in data.chunks_exact(32).zip(expected) {
+        assert_eq!(record[24..28], color);
+        assert_eq!(record[28..32], rotation);
+    }
+}
+
 impl SplatFormat for SplatB {
     fn is_format(path: &Path) -> FormatResult {
-        let extension = path.extension().unwrap().to_str().unwrap();
-        if extension != "splat" {
+        let inner = inner_path(path);
+        let extension = inner.extension().and_then(|e| e.to_str());
+        if extension != Some("splat") {
             return FormatResult::No("Extension is not splat".to_string());
         }
         if !path.exists() {
             return FormatResult::Maybe(Some(0.333));
         }
-        let size = std::fs::metadata(path).unwrap().len();
-        if size % 32_u64 == 0 {
+        let Ok(size) = input_size(path) else {
+            return FormatResult::No("Can not read file".to_string());
+        };
+        if size.is_multiple_of(32_u64) {
             FormatResult::Maybe(Some(0.666))
         } else {
             FormatResult::No("Size is not a multiple of 32".to_string())
@@ -43,7 +67,7 @@ impl SplatFormat for SplatB {
     }
 
     fn load(path: &Path) -> Result<Vec<UberSplat>> {
-        let data = std::fs::read(path)?;
+        let data = read_input(path)?;
         let chunk_size = 32;
         let splats = data
             .chunks_exact(chunk_size)
@@ -62,58 +86,95 @@ impl SplatFormat for SplatB {
     }
 
     fn save(splats: &[UberSplat], path: &Path) -> Result<()> {
-        let mut file = File::create(path)?;
+        let mut file = create_output(path)?;
         for splat in splats {
             let splat: SplatB = splat.to_owned().into();
             let bytes: &[u8] = bytemuck::bytes_of(&splat);
             file.write_all(bytes)?;
         }
+        file.finish()?;
         Ok(())
     }
 }
 
+/// Quantise a unit quaternion component in [-1, 1] to a byte as `v * 128 + 128`, rounded and clamped like
+/// `Color::to_linear_u8`.
+fn quantise_rotation(value: f32) -> u8 {
+    (value * 128.0 + 128.0).round_ties_even().clamp(0.0, 255.0) as u8
+}
+
+fn dequantise_rotation(value: u8) -> f32 {
+    (value as f32 - 128.0) / 128.0
+}
+
 impl From<SplatB> for UberSplat {
     fn from(splat: SplatB) -> Self {
-        // TODO: SO UGLY
-        let color = splat.color.xyz();
-        let color = Vector3::new(
-            color.x as f32 / 255.0,
-            color.y as f32 / 255.0,
-            color.z as f32 / 255.0,
-        );
-        let color = srgb_to_linear(color);
-        let color = Vector3::new(
-            (color.x * 255.0) as u8,
-            (color.y * 255.0) as u8,
-            (color.z * 255.0) as u8,
-        );
-        let color = Color::LinearU8(color);
+        // Colors are stored as they are displayed, so they are kept as is.
+        let color = Color::LinearU8(splat.color.xyz());
         let opacity = Opacity::LinearU8(splat.color.w);
         let scale = Scale::LinearFloat(splat.scale);
-        let rotation = Quaternion::new(
-            splat.rotation[1] as f32 / 255.0 - 0.5,
-            splat.rotation[2] as f32 / 255.0 - 0.5,
-            splat.rotation[3] as f32 / 255.0 - 0.5,
-            splat.rotation[0] as f32 / 255.0 - 0.5,
-        );
+        let [w, x, y, z] = splat.rotation.map(dequantise_rotation).into();
+        let rotation = Quaternion::new(w, x, y, z);
         UberSplat::new(splat.position, None, color, opacity, scale, rotation)
     }
 }
 
+/// Encodes like the PLY conversion in antimatter15's viewer: linear scales, DC color and sigmoid opacity rounded to
+/// the nearest byte by its `Uint8ClampedArray`, and the normalised quaternion as `q * 128 + 128`. Its `convert.py`
+/// truncates instead, so can write bytes one lower.
 impl From<UberSplat> for SplatB {
     fn from(uber_splat: UberSplat) -> Self {
         let rgb = uber_splat.color.to_linear_u8();
         let alpha = uber_splat.opacity.to_linear_u8();
         let color = Vector4::new(rgb.x, rgb.y, rgb.z, alpha);
         let scale = uber_splat.scale.to_linear_float();
-        let imag = uber_splat
-            .rotation
-            .imag()
-            .iter()
-            .map(|v| ((v + 0.5) * 255.0) as u8)
-            .collect::<Vec<u8>>();
-        let real = (uber_splat.rotation.scalar() + 0.5 * 255.0) as u8;
-        let rotation = Vector4::new(real, imag[0], imag[1], imag[2]);
+        let rotation = uber_splat.unit_rotation();
+        let rotation =
+            Vector4::new(rotation.w, rotation.i, rotation.j, rotation.k).map(quantise_rotation);
         SplatB::new(uber_splat.position, scale, color, rotation)
     }
 }
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_splat_b_round_trip_error_is_bounded(
+        position in proptest::array::uniform3(-1e4f32..1e4),
+        log_scale in proptest::array::uniform3(-12f32..4.0),
+        dc in proptest::array::uniform3(-3f32..3.0),
+        logit in -12f32..12.0,
+        rotation in proptest::array::uniform4(-1f32..1.0),
+    ) {
+        let rotation = Quaternion::new(rotation[0], rotation[1], rotation[2], rotation[3]);
+        proptest::prop_assume!(rotation.norm() > 1e-3);
+        let splat = UberSplat::new(
+            Vector3::from(position),
+            None,
+            Color::FirstOrderSphericalHarmonic(Vector3::from(dc)),
+            Opacity::LogitFloat(logit),
+            Scale::Exponent(Vector3::from(log_scale)),
+            rotation,
+        );
+        let encoded = SplatB::from(splat.clone());
+        let decoded = UberSplat::from(bytemuck::pod_read_unaligned::<SplatB>(bytemuck::bytes_of(&encoded)));
+
+        proptest::prop_assert_eq!(decoded.position, splat.position);
+        let scale = splat.scale.to_linear_float();
+        let scale_error = (decoded.scale.to_linear_float() - scale).abs().max() / scale.max();
+        proptest::prop_assert!(scale_error < 1e-6);
+        let color_error = (decoded.color.to_linear_float() - splat.color.to_linear_float()).abs().max();
+        proptest::prop_assert!(color_error <= 0.5 / 255.0 + 1e-6);
+        let alpha_error = (decoded.opacity.to_linear_float() - splat.opacity.to_linear_float()).abs();
+        proptest::prop_assert!(alpha_error <= 0.5 / 255.0 + 1e-6);
+        // Components are off by at most 1/128, since 1.0 clamps to byte 255, which is at most about 1.8 degrees.
+        let original = nalgebra::UnitQuaternion::from_quaternion(splat.rotation);
+        let angle = original.angle_to(&nalgebra::UnitQuaternion::from_quaternion(decoded.rotation));
+        proptest::prop_assert!(angle.to_degrees() < 2.0, "rotation error {} degrees", angle.to_degrees());
+
+        // Decoded splats encode back to the same bytes, except that renormalising may move rotations by one step.
+        let reencoded = SplatB::from(decoded);
+        proptest::prop_assert_eq!(reencoded.color, encoded.color);
+        let steps = reencoded.rotation.zip_map(&encoded.rotation, |a, b| a.abs_diff(b));
+        proptest::prop_assert!(steps.max() <= 1);
+    }
+}
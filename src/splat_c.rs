@@ -1,11 +1,9 @@
 use crate::*;
 use anyhow::Result;
 use bytemuck::{Pod, Zeroable};
-use core::panic;
 use derive_new::new as New;
 use half::f16;
-use nalgebra::{Vector3, Vector4};
-use std::fs::File;
+use nalgebra::{Matrix3, UnitQuaternion, Vector3, Vector4};
 use std::io::Write;
 use std::path::Path;
 
@@ -27,39 +25,96 @@ fn test_splat_c_size() {
     assert_eq!(std::mem::size_of::<SplatC>(), 26);
 }
 
+#[cfg(test)]
+#[test]
+fn test_splat_c_round_trip() {
+    let splat = UberSplat::new(
+        Vector3::new(1.0, 2.0, -3.0),
+        None,
+        Color::LinearFloat(Vector3::new(0.8, 0.5, 0.1)),
+        Opacity::LinearFloat(0.75),
+        Scale::LinearFloat(Vector3::new(0.3, 0.1, 0.02)),
+        nalgebra::Quaternion::new(0.9, 0.1, -0.3, 0.2),
+    );
+    let stored = SplatC::from(splat.clone());
+    // Colors are stored as linear light.
+    assert!((stored.color.x.to_f32() - srgb_to_linear(0.8)).abs() < 1e-3);
+    let loaded = UberSplat::from(stored);
+    assert!((loaded.color.to_linear_float() - splat.color.to_linear_float()).norm() < 2e-3);
+    assert!((loaded.position - splat.position).norm() < 2e-3);
+    // Scales and rotations may come back reordered, so compare the covariances they describe.
+    let (a, b) = (splat.to_cov(), loaded.to_cov());
+    assert!((a.0 - b.0).norm() + (a.1 - b.1).norm() < 1e-4);
+}
+
 impl SplatFormat for SplatC {
     fn is_format(path: &Path) -> FormatResult {
         if !path.exists() {
-            let extension = path.extension().unwrap().to_str().unwrap();
-            if extension == "splatc" {
+            let extension = inner_path(path).extension().map(|e| e.to_owned());
+            if extension.as_deref() == Some("splatc".as_ref()) {
                 return FormatResult::Maybe(Some(0.333));
             }
             return FormatResult::No("Extension is not splatc".to_string());
         }
 
-        let size = std::fs::metadata(path).unwrap().len();
-        if size % 26 == 0 {
+        let Ok(size) = input_size(path) else {
+            return FormatResult::No("Can not read file".to_string());
+        };
+        if size.is_multiple_of(26) {
             FormatResult::Maybe(Some(0.666))
         } else {
             FormatResult::No("Size is not a multiple of 26".to_string())
         }
     }
 
-    fn load(_: &Path) -> Result<Vec<UberSplat>> {
-        panic!("Not implemented");
+    fn load(path: &Path) -> Result<Vec<UberSplat>> {
+        let data = read_input(path)?;
+        let splats = data
+            .chunks_exact(std::mem::size_of::<SplatC>())
+            .map(bytemuck::pod_read_unaligned::<SplatC>)
+            .map(UberSplat::from)
+            .collect();
+        Ok(splats)
     }
 
     fn save(splats: &[UberSplat], path: &Path) -> Result<()> {
-        let mut file = File::create(path)?;
+        let mut file = create_output(path)?;
         for splat in splats {
             let splat: SplatC = splat.clone().into();
             let bytes: &[u8] = bytemuck::bytes_of(&splat);
             file.write_all(bytes)?;
         }
+        file.finish()?;
         Ok(())
     }
 }
 
+impl From<SplatC> for UberSplat {
+    /// Recover the scale and rotation from the covariance's eigen decomposition. Which of several equivalent
+    /// rotations is found is arbitrary.
+    fn from(splat: SplatC) -> Self {
+        let [a, b] = [splat.cov_a, splat.cov_b].map(|v| v.map(f16::to_f32));
+        let cov = Matrix3::new(a.x, a.y, a.z, a.y, b.x, b.y, a.z, b.y, b.z);
+        let eigen = cov.symmetric_eigen();
+        let mut axes = eigen.eigenvectors;
+        if axes.determinant() < 0.0 {
+            axes.set_column(2, &-axes.column(2));
+        }
+        let rotation = UnitQuaternion::from_matrix(&axes).into_inner();
+        let scale = eigen.eigenvalues.map(|v| v.max(0.0).sqrt());
+        let color = splat.color.map(f16::to_f32);
+        let rgb = SplatFormats::SplatC.color_space().decode(color.xyz());
+        UberSplat::new(
+            splat.position.map(f16::to_f32),
+            None,
+            Color::LinearFloat(rgb),
+            Opacity::LinearFloat(color.w),
+            Scale::LinearFloat(scale),
+            rotation,
+        )
+    }
+}
+
 impl From<UberSplat> for SplatC {
     fn from(uber_splat: UberSplat) -> Self {
         let position = Vector3::new(
@@ -67,7 +122,9 @@ impl From<UberSplat> for SplatC {
             f16::from_f32(uber_splat.position.y),
             f16::from_f32(uber_splat.position.z),
         );
-        let rgb = uber_splat.color.to_linear_float();
+        let rgb = SplatFormats::SplatC
+            .color_space()
+            .encode(uber_splat.color.to_linear_float());
         let alpha = uber_splat.opacity.to_linear_float();
         let color = Vector4::new(
             f16::from_f32(rgb.x),
@@ -17,11 +17,16 @@ pub trait SplatFormat {
 }
 
 pub fn load_splats(path: &Path) -> Result<Vec<UberSplat>> {
-    let format = guess_format(path).unwrap();
+    let Some(format) = guess_format(path) else {
+        anyhow::bail!("Unknown format for {}", path.display());
+    };
     match format {
         SplatFormats::SplatA => SplatA::load(path),
         SplatFormats::SplatB => SplatB::load(path),
         SplatFormats::SplatC => SplatC::load(path),
+        SplatFormats::SplatVq => SplatVq::load(path),
+        SplatFormats::SplatX => SplatX::load(path),
+        SplatFormats::Splat2d => Splat2d::load(path),
     }
 }
 
@@ -30,5 +35,8 @@ pub fn save_splats(splats: Vec<UberSplat>, format: SplatFormats, path: &Path) ->
         SplatFormats::SplatA => SplatA::save(&splats, path),
         SplatFormats::SplatB => SplatB::save(&splats, path),
         SplatFormats::SplatC => SplatC::save(&splats, path),
+        SplatFormats::SplatVq => SplatVq::save(&splats, path),
+        SplatFormats::SplatX => SplatX::save(&splats, path),
+        SplatFormats::Splat2d => Splat2d::save(&splats, path),
     }
 }
@@ -0,0 +1,265 @@
+use crate::*;
+use anyhow::Result;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use nalgebra::Vector3;
+use std::io::{Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"SPLATVQ1";
+
+/// Codebook compressed `.splatvq` files written from a `VqScene`.
+///
+/// After the magic, a little endian header holds the splat count, SH degree and which of scales and rotations are
+/// quantised, followed by the codebooks (size, dimension and entries) and then one array per attribute. Codebook
+/// indices are `u16`.
+pub struct SplatVq;
+
+fn write_floats(writer: &mut impl Write, values: &[f32]) -> Result<()> {
+    for value in values {
+        writer.write_f32::<LittleEndian>(*value)?;
+    }
+    Ok(())
+}
+
+fn read_floats(reader: &mut impl Read, count: usize) -> Result<Vec<f32>> {
+    Ok(read_values(reader, count, |reader, _| {
+        reader.read_f32::<LittleEndian>()
+    })?)
+}
+
+fn write_codebook(writer: &mut impl Write, codebook: &Codebook) -> Result<()> {
+    writer.write_u32::<LittleEndian>(codebook.len() as u32)?;
+    writer.write_u32::<LittleEndian>(codebook.dimension as u32)?;
+    write_floats(writer, &codebook.entries)
+}
+
+fn read_codebook(reader: &mut impl Read, dimension: usize) -> Result<Codebook> {
+    let size = reader.read_u32::<LittleEndian>()? as usize;
+    let stored_dimension = reader.read_u32::<LittleEndian>()? as usize;
+    if stored_dimension != dimension || size > MAX_CODEBOOK_SIZE {
+        anyhow::bail!(
+            "Invalid codebook of {} entries of dimension {}",
+            size,
+            stored_dimension
+        );
+    }
+    let entries = read_floats(reader, size * dimension)?;
+    Ok(Codebook { dimension, entries })
+}
+
+fn write_indices(writer: &mut impl Write, indices: &[u32]) -> Result<()> {
+    for index in indices {
+        writer.write_u16::<LittleEndian>(*index as u16)?;
+    }
+    Ok(())
+}
+
+fn read_indices(reader: &mut impl Read, count: usize, codebook: &Codebook) -> Result<Vec<u32>> {
+    let indices = read_values(reader, count, |reader, _| reader.read_u16::<LittleEndian>())?;
+    if indices.iter().any(|i| *i as usize >= codebook.len()) {
+        anyhow::bail!("Codebook index out of range");
+    }
+    Ok(indices.into_iter().map(u32::from).collect())
+}
+
+fn write_attribute(writer: &mut impl Write, attribute: &VqAttribute) -> Result<()> {
+    match attribute {
+        VqAttribute::Raw(values) => write_floats(writer, values),
+        VqAttribute::Quantised(_, indices) => write_indices(writer, indices),
+    }
+}
+
+fn read_attribute(
+    reader: &mut impl Read,
+    count: usize,
+    dimension: usize,
+    codebook: Option<Codebook>,
+) -> Result<VqAttribute> {
+    Ok(match codebook {
+        Some(codebook) => {
+            let indices = read_indices(reader, count, &codebook)?;
+            VqAttribute::Quantised(codebook, indices)
+        }
+        None => VqAttribute::Raw(read_floats(reader, count * dimension)?),
+    })
+}
+
+fn read_vectors(reader: &mut impl Read, count: usize) -> Result<Vec<Vector3<f32>>> {
+    let values = read_floats(reader, count * 3)?;
+    Ok(values
+        .chunks_exact(3)
+        .map(Vector3::from_column_slice)
+        .collect())
+}
+
+impl SplatVq {
+    pub fn write(scene: &VqScene, writer: &mut impl Write) -> Result<()> {
+        let codebook = |attribute: &VqAttribute| match attribute {
+            VqAttribute::Quantised(codebook, _) => Some(codebook.clone()),
+            VqAttribute::Raw(_) => None,
+        };
+        let (scales, rotations) = (codebook(&scene.scales), codebook(&scene.rotations));
+        for codebook in [&scales, &rotations, &scene.sh.as_ref().map(|s| s.0.clone())]
+            .into_iter()
+            .flatten()
+        {
+            if codebook.len() > MAX_CODEBOOK_SIZE {
+                anyhow::bail!("Codebooks can have at most {} entries", MAX_CODEBOOK_SIZE);
+            }
+        }
+
+        writer.write_all(MAGIC)?;
+        writer.write_u32::<LittleEndian>(scene.len() as u32)?;
+        writer.write_u8(scene.sh_degree as u8)?;
+        writer.write_u8(scales.is_some() as u8 | (rotations.is_some() as u8) << 1)?;
+        writer.write_u16::<LittleEndian>(0)?;
+        for codebook in [&scales, &rotations].into_iter().flatten() {
+            write_codebook(writer, codebook)?;
+        }
+        if let Some((codebook, _)) = &scene.sh {
+            write_codebook(writer, codebook)?;
+        }
+
+        write_floats(
+            writer,
+            &scene
+                .positions
+                .iter()
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>(),
+        )?;
+        write_floats(
+            writer,
+            &scene.dc.iter().flatten().copied().collect::<Vec<_>>(),
+        )?;
+        write_floats(writer, &scene.opacities)?;
+        write_attribute(writer, &scene.scales)?;
+        write_attribute(writer, &scene.rotations)?;
+        if let Some((_, indices)) = &scene.sh {
+            write_indices(writer, indices)?;
+        }
+        Ok(())
+    }
+
+    pub fn read(reader: &mut impl Read) -> Result<VqScene> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            anyhow::bail!("Not a splatvq file");
+        }
+        let count = reader.read_u32::<LittleEndian>()? as usize;
+        let sh_degree = reader.read_u8()? as usize;
+        let flags = reader.read_u8()?;
+        reader.read_u16::<LittleEndian>()?;
+        if sh_degree > MAX_SH_DEGREE {
+            anyhow::bail!("Invalid spherical harmonic degree {}", sh_degree);
+        }
+        let scales = (flags & 1 != 0)
+            .then(|| read_codebook(reader, 3))
+            .transpose()?;
+        let rotations = (flags & 2 != 0)
+            .then(|| read_codebook(reader, 4))
+            .transpose()?;
+        let sh_codebook = (sh_degree > 0)
+            .then(|| read_codebook(reader, 3 * (sh_coefficient_count(sh_degree) - 1)))
+            .transpose()?;
+
+        let positions = read_vectors(reader, count)?;
+        let dc = read_vectors(reader, count)?;
+        let opacities = read_floats(reader, count)?;
+        let scales = read_attribute(reader, count, 3, scales)?;
+        let rotations = read_attribute(reader, count, 4, rotations)?;
+        let sh = match sh_codebook {
+            Some(codebook) => {
+                let indices = read_indices(reader, count, &codebook)?;
+                Some((codebook, indices))
+            }
+            None => None,
+        };
+        Ok(VqScene {
+            positions,
+            dc,
+            opacities,
+            scales,
+            rotations,
+            sh_degree,
+            sh,
+        })
+    }
+
+    /// Encode with `options` and write to `path`, returning the encoded scene.
+    pub fn save_with_options(
+        splats: &[UberSplat],
+        path: &Path,
+        options: &VqOptions,
+    ) -> Result<VqScene> {
+        let scene = VqScene::encode(splats, options)?;
+        let mut writer = create_output(path)?;
+        SplatVq::write(&scene, &mut writer)?;
+        writer.finish()?;
+        Ok(scene)
+    }
+}
+
+impl SplatFormat for SplatVq {
+    fn is_format(path: &Path) -> FormatResult {
+        let inner = inner_path(path);
+        let extension = inner.extension().and_then(|e| e.to_str());
+        if !path.exists() {
+            return match extension {
+                Some("splatvq") => FormatResult::Maybe(Some(0.666)),
+                _ => FormatResult::No("Extension is not splatvq".to_string()),
+            };
+        }
+        let mut magic = [0; 8];
+        let read = open_input(path).and_then(|mut file| file.read_exact(&mut magic));
+        if read.is_ok() && &magic == MAGIC {
+            FormatResult::Yes
+        } else {
+            FormatResult::No("Missing splatvq magic".to_string())
+        }
+    }
+
+    fn load(path: &Path) -> Result<Vec<UberSplat>> {
+        let mut reader = open_input(path)?;
+        Ok(SplatVq::read(&mut reader)?.decode())
+    }
+
+    fn save(splats: &[UberSplat], path: &Path) -> Result<()> {
+        SplatVq::save_with_options(splats, path, &VqOptions::default())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_splat_vq_write_read() {
+    let splats = (0..50)
+        .map(|i| {
+            UberSplat::new(
+                Vector3::new(i as f32, 1.0, 2.0),
+                None,
+                Color::SphericalHarmonic(Vector3::repeat(0.1), vec![i as f32 * 0.01; 9]),
+                Opacity::LogitFloat(0.5),
+                Scale::Exponent(Vector3::new(-1.0, -2.0, i as f32 * -0.1)),
+                nalgebra::Quaternion::identity(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let options = VqOptions {
+        sh_codebook: 16,
+        rotation_codebook: Some(4),
+        seed: Some(5),
+        ..Default::default()
+    };
+    let scene = VqScene::encode(&splats, &options).unwrap();
+    let mut bytes = Vec::new();
+    SplatVq::write(&scene, &mut bytes).unwrap();
+    let read = SplatVq::read(&mut bytes.as_slice()).unwrap();
+    assert_eq!(read, scene);
+    assert_eq!(read.decode()[7].position, splats[7].position);
+    assert!(SplatVq::read(&mut &bytes[..40]).is_err());
+    bytes[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(SplatVq::read(&mut bytes.as_slice()).is_err());
+}
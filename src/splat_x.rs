@@ -0,0 +1,411 @@
+use crate::*;
+use anyhow::Result;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use clap::ValueEnum;
+use half::f16;
+use nalgebra::{Quaternion, Vector3};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+use strum::{EnumIter, IntoEnumIterator};
+
+const MAGIC: &[u8; 8] = b"SPLATX01";
+
+/// How one attribute's components are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AttributeCodec {
+    /// 32 bit floats
+    F32,
+    /// 16 bit floats
+    F16,
+    /// 16 bit fixed point between per-component bounds
+    Fixed16,
+    /// 8 bit quantised between per-component bounds
+    U8,
+}
+
+impl AttributeCodec {
+    fn tag(&self) -> u8 {
+        *self as u8
+    }
+
+    fn from_tag(tag: u8) -> Option<AttributeCodec> {
+        AttributeCodec::value_variants()
+            .iter()
+            .copied()
+            .find(|c| c.tag() == tag)
+    }
+
+    /// The largest stored integer for codecs with bounds.
+    fn levels(&self) -> Option<f32> {
+        match self {
+            AttributeCodec::Fixed16 => Some(u16::MAX as f32),
+            AttributeCodec::U8 => Some(u8::MAX as f32),
+            _ => None,
+        }
+    }
+}
+
+/// The attributes of a splat, in the order they are stored. Values are the 3DGS parameters, except that opacity is
+/// linear so that it has natural bounds, and rotations are normalised `w, x, y, z` quaternions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, ValueEnum)]
+pub enum SplatAttribute {
+    Position,
+    /// Log scale
+    Scale,
+    Rotation,
+    Opacity,
+    /// DC spherical harmonic term
+    Color,
+    /// Higher spherical harmonic bands
+    ShRest,
+}
+
+impl SplatAttribute {
+    pub fn dimension(&self, sh_degree: usize) -> usize {
+        match self {
+            SplatAttribute::Position | SplatAttribute::Scale | SplatAttribute::Color => 3,
+            SplatAttribute::Rotation => 4,
+            SplatAttribute::Opacity => 1,
+            SplatAttribute::ShRest => 3 * (sh_coefficient_count(sh_degree) - 1),
+        }
+    }
+}
+
+/// A codec for each `SplatAttribute`, in storage order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplatXCodecs(pub [AttributeCodec; 6]);
+
+impl SplatXCodecs {
+    pub fn get(&self, attribute: SplatAttribute) -> AttributeCodec {
+        self.0[attribute as usize]
+    }
+
+    pub fn set(&mut self, attribute: SplatAttribute, codec: AttributeCodec) {
+        self.0[attribute as usize] = codec;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum SplatXPreset {
+    /// Fixed point positions, half float scales and colors, 8 bit rotations, opacities and SH
+    Web,
+    /// Fixed point positions and 8 bit everything else
+    Mobile,
+    /// Lossless 32 bit floats
+    Archive,
+}
+
+impl SplatXPreset {
+    pub fn codecs(&self) -> SplatXCodecs {
+        use AttributeCodec::*;
+        SplatXCodecs(match self {
+            SplatXPreset::Web => [Fixed16, F16, U8, U8, F16, U8],
+            SplatXPreset::Mobile => [Fixed16, U8, U8, U8, U8, U8],
+            SplatXPreset::Archive => [F32; 6],
+        })
+    }
+}
+
+/// A codec for one attribute, parsed from `attribute=codec`, e.g. `sh-rest=f16`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CodecOverride {
+    pub attribute: SplatAttribute,
+    pub codec: AttributeCodec,
+}
+
+impl FromStr for CodecOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (attribute, codec) = s
+            .split_once('=')
+            .ok_or(format!("Expected `attribute=codec`, got `{}`", s))?;
+        Ok(CodecOverride {
+            attribute: SplatAttribute::from_str(attribute.trim(), true)?,
+            codec: AttributeCodec::from_str(codec.trim(), true)?,
+        })
+    }
+}
+
+/// `.splatx` files, where each attribute has its own codec recorded in the header.
+///
+/// After the magic, the little endian header holds the splat count and SH degree, then for each attribute its codec
+/// tag and, for bounded codecs, per-component minimums and maximums. One array per attribute follows.
+pub struct SplatX;
+
+/// Attribute values of every splat, `dimension` floats per splat.
+fn attribute_values(splats: &[UberSplat], attribute: SplatAttribute, sh_degree: usize) -> Vec<f32> {
+    splats
+        .iter()
+        .flat_map(|splat| -> Vec<f32> {
+            match attribute {
+                SplatAttribute::Position => splat.position.iter().copied().collect(),
+                SplatAttribute::Scale => SplatA::from(splat).scale.iter().copied().collect(),
+                SplatAttribute::Rotation => {
+                    let rotation = splat.unit_rotation();
+                    vec![rotation.w, rotation.i, rotation.j, rotation.k]
+                }
+                SplatAttribute::Opacity => vec![splat.opacity.to_linear_float()],
+                SplatAttribute::Color => SplatA::from(splat).f_dc.iter().copied().collect(),
+                SplatAttribute::ShRest => match &splat.color {
+                    Color::SphericalHarmonic(_, rest) => resize_sh_rest(rest, sh_degree),
+                    _ => resize_sh_rest(&[], sh_degree),
+                },
+            }
+        })
+        .collect()
+}
+
+fn write_attribute(
+    writer: &mut impl Write,
+    values: &[f32],
+    dimension: usize,
+    codec: AttributeCodec,
+    bounds: &[(f32, f32)],
+) -> Result<()> {
+    for (index, value) in values.iter().enumerate() {
+        match codec {
+            AttributeCodec::F32 => writer.write_f32::<LittleEndian>(*value)?,
+            AttributeCodec::F16 => write_f16(writer, f16::from_f32(*value))?,
+            AttributeCodec::Fixed16 | AttributeCodec::U8 => {
+                let (min, max) = bounds[index % dimension];
+                let levels = codec.levels().unwrap();
+                let unit = if max > min {
+                    (value - min) / (max - min)
+                } else {
+                    0.0
+                };
+                let level = (unit * levels).round().clamp(0.0, levels);
+                if codec == AttributeCodec::U8 {
+                    writer.write_u8(level as u8)?;
+                } else {
+                    writer.write_u16::<LittleEndian>(level as u16)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_attribute(
+    reader: &mut impl Read,
+    count: usize,
+    dimension: usize,
+    codec: AttributeCodec,
+    bounds: &[(f32, f32)],
+) -> Result<Vec<f32>> {
+    Ok(read_values(reader, count * dimension, |reader, index| {
+        Ok(match codec {
+            AttributeCodec::F32 => reader.read_f32::<LittleEndian>()?,
+            AttributeCodec::F16 => read_f16(reader)?.to_f32(),
+            AttributeCodec::Fixed16 | AttributeCodec::U8 => {
+                let level = if codec == AttributeCodec::U8 {
+                    reader.read_u8()? as f32
+                } else {
+                    reader.read_u16::<LittleEndian>()? as f32
+                };
+                let (min, max) = bounds[index % dimension];
+                min + level / codec.levels().unwrap() * (max - min)
+            }
+        })
+    })?)
+}
+
+impl SplatX {
+    pub fn write(
+        splats: &[UberSplat],
+        codecs: &SplatXCodecs,
+        writer: &mut impl Write,
+    ) -> Result<()> {
+        let sh_degree = splats
+            .iter()
+            .filter_map(|s| s.color.sh_degree())
+            .max()
+            .unwrap_or(0);
+        writer.write_all(MAGIC)?;
+        writer.write_u32::<LittleEndian>(splats.len() as u32)?;
+        writer.write_u8(sh_degree as u8)?;
+
+        let mut attributes = Vec::new();
+        for attribute in SplatAttribute::iter() {
+            let dimension = attribute.dimension(sh_degree);
+            let values = attribute_values(splats, attribute, sh_degree);
+            let codec = codecs.get(attribute);
+            writer.write_u8(codec.tag())?;
+            let mut bounds = vec![(0.0, 0.0); dimension];
+            if codec.levels().is_some() {
+                for (component, bound) in bounds.iter_mut().enumerate() {
+                    let component_values = values
+                        .iter()
+                        .skip(component)
+                        .step_by(dimension)
+                        .filter(|v| v.is_finite());
+                    *bound = component_values
+                        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+                            (min.min(*v), max.max(*v))
+                        });
+                    if bound.0 > bound.1 {
+                        *bound = (0.0, 0.0);
+                    }
+                    writer.write_f32::<LittleEndian>(bound.0)?;
+                    writer.write_f32::<LittleEndian>(bound.1)?;
+                }
+            }
+            attributes.push((values, dimension, codec, bounds));
+        }
+        for (values, dimension, codec, bounds) in attributes {
+            write_attribute(writer, &values, dimension, codec, &bounds)?;
+        }
+        Ok(())
+    }
+
+    pub fn read(reader: &mut impl Read) -> Result<Vec<UberSplat>> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            anyhow::bail!("Not a splatx file");
+        }
+        let count = reader.read_u32::<LittleEndian>()? as usize;
+        let sh_degree = reader.read_u8()? as usize;
+        if sh_degree > MAX_SH_DEGREE {
+            anyhow::bail!("Invalid spherical harmonic degree {}", sh_degree);
+        }
+        let mut headers = Vec::new();
+        for attribute in SplatAttribute::iter() {
+            let tag = reader.read_u8()?;
+            let codec = AttributeCodec::from_tag(tag)
+                .ok_or_else(|| anyhow::anyhow!("Unknown codec {} for {:?}", tag, attribute))?;
+            let dimension = attribute.dimension(sh_degree);
+            let mut bounds = vec![(0.0, 0.0); dimension];
+            if codec.levels().is_some() {
+                for bound in bounds.iter_mut() {
+                    *bound = (
+                        reader.read_f32::<LittleEndian>()?,
+                        reader.read_f32::<LittleEndian>()?,
+                    );
+                }
+            }
+            headers.push((dimension, codec, bounds));
+        }
+        let values = headers
+            .iter()
+            .map(|(dimension, codec, bounds)| {
+                read_attribute(reader, count, *dimension, *codec, bounds)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let [positions, scales, rotations, opacities, colors, rests] = values.as_slice() else {
+            unreachable!()
+        };
+
+        let splats = (0..count)
+            .map(|i| {
+                let vector = |values: &[f32]| Vector3::from_column_slice(&values[i * 3..i * 3 + 3]);
+                let dc = vector(colors);
+                let color = if sh_degree > 0 {
+                    let dimension = SplatAttribute::ShRest.dimension(sh_degree);
+                    Color::SphericalHarmonic(dc, rests[i * dimension..(i + 1) * dimension].to_vec())
+                } else {
+                    Color::FirstOrderSphericalHarmonic(dc)
+                };
+                let [w, x, y, z] = rotations[i * 4..i * 4 + 4] else {
+                    unreachable!()
+                };
+                UberSplat::new(
+                    vector(positions),
+                    None,
+                    color,
+                    Opacity::LinearFloat(opacities[i].clamp(0.0, 1.0)),
+                    Scale::Exponent(vector(scales)),
+                    Quaternion::new(w, x, y, z),
+                )
+            })
+            .collect();
+        Ok(splats)
+    }
+
+    pub fn save_with_codecs(
+        splats: &[UberSplat],
+        path: &Path,
+        codecs: &SplatXCodecs,
+    ) -> Result<()> {
+        let mut writer = create_output(path)?;
+        SplatX::write(splats, codecs, &mut writer)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+impl SplatFormat for SplatX {
+    fn is_format(path: &Path) -> FormatResult {
+        let inner = inner_path(path);
+        let extension = inner.extension().and_then(|e| e.to_str());
+        if !path.exists() {
+            return match extension {
+                Some("splatx") => FormatResult::Maybe(Some(0.666)),
+                _ => FormatResult::No("Extension is not splatx".to_string()),
+            };
+        }
+        let mut magic = [0; 8];
+        let read = open_input(path).and_then(|mut file| file.read_exact(&mut magic));
+        if read.is_ok() && &magic == MAGIC {
+            FormatResult::Yes
+        } else {
+            FormatResult::No("Missing splatx magic".to_string())
+        }
+    }
+
+    fn load(path: &Path) -> Result<Vec<UberSplat>> {
+        SplatX::read(&mut open_input(path)?)
+    }
+
+    fn save(splats: &[UberSplat], path: &Path) -> Result<()> {
+        SplatX::save_with_codecs(splats, path, &SplatXPreset::Web.codecs())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_splat_x_presets_round_trip() {
+    let splats = (0..200)
+        .map(|i| {
+            let t = i as f32 / 199.0;
+            UberSplat::new(
+                Vector3::new(t * 10.0, -t, 3.0),
+                None,
+                Color::SphericalHarmonic(Vector3::new(t, 0.5, -t), vec![t * 0.1 - 0.05; 24]),
+                Opacity::LinearFloat(t),
+                Scale::Exponent(Vector3::new(-t * 4.0, -2.0, -1.0)),
+                Quaternion::new(1.0, t, 0.0, 0.0),
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut bytes = Vec::new();
+    SplatX::write(&splats, &SplatXPreset::Archive.codecs(), &mut bytes).unwrap();
+    let archive = SplatX::read(&mut bytes.as_slice()).unwrap();
+    let diff = diff_scenes(&splats, &archive, DiffMatching::Index);
+    // Rotations are normalised, which is within float precision of the same rotation.
+    assert!(diff.attributes.iter().all(|e| e.max < 1e-4));
+
+    let archive_size = bytes.len();
+    bytes.clear();
+    SplatX::write(&splats, &SplatXPreset::Mobile.codecs(), &mut bytes).unwrap();
+    assert!(bytes.len() * 3 < archive_size);
+    let mobile = SplatX::read(&mut bytes.as_slice()).unwrap();
+    // 8 bit quantisation is within half a step of each attribute's range.
+    let opacity = |s: &UberSplat| s.opacity.to_linear_float();
+    assert!(splats
+        .iter()
+        .zip(&mobile)
+        .all(|(a, b)| (opacity(a) - opacity(b)).abs() <= 0.5 / 255.0 + 1e-6));
+    assert!((mobile[199].position.x - 10.0).abs() < 1e-3);
+    assert_eq!(mobile[0].color.sh_degree(), Some(2));
+
+    // Claim more splats than the file holds.
+    bytes[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(SplatX::read(&mut bytes.as_slice()).is_err());
+
+    let parsed = "sh-rest=f16".parse::<CodecOverride>().unwrap();
+    assert_eq!(parsed.attribute, SplatAttribute::ShRest);
+    assert_eq!(parsed.codec, AttributeCodec::F16);
+}
@@ -0,0 +1,218 @@
+use crate::*;
+use nalgebra::Vector3;
+use serde::Serialize;
+
+/// Percentiles reported for every distribution.
+pub const PERCENTILES: [f32; 7] = [1.0, 5.0, 25.0, 50.0, 75.0, 95.0, 99.0];
+
+/// Summary statistics and a histogram of one attribute.
+#[derive(Debug, Clone, Serialize)]
+pub struct Distribution {
+    pub name: String,
+    pub count: usize,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    /// Values at each of `PERCENTILES`.
+    pub percentiles: Vec<f32>,
+    /// Counts in equal width bins from `min` to `max`.
+    pub histogram: Vec<usize>,
+}
+
+impl Distribution {
+    /// Non-finite values are ignored; they are counted as degenerate splats instead.
+    pub fn new(name: &str, values: impl IntoIterator<Item = f32>, bins: usize) -> Distribution {
+        let mut values = values
+            .into_iter()
+            .filter(|v| v.is_finite())
+            .collect::<Vec<f32>>();
+        values.sort_by(f32::total_cmp);
+        let count = values.len();
+        if count == 0 {
+            return Distribution {
+                name: name.to_string(),
+                count,
+                min: 0.0,
+                max: 0.0,
+                mean: 0.0,
+                percentiles: vec![0.0; PERCENTILES.len()],
+                histogram: vec![0; bins],
+            };
+        }
+        let (min, max) = (values[0], values[count - 1]);
+        let mean = (values.iter().map(|v| *v as f64).sum::<f64>() / count as f64) as f32;
+        let percentiles = PERCENTILES
+            .iter()
+            .map(|p| values[((p / 100.0 * (count - 1) as f32).round() as usize).min(count - 1)])
+            .collect();
+        let mut histogram = vec![0; bins];
+        let width = (max - min) / bins as f32;
+        for value in &values {
+            let bin = if width > 0.0 {
+                (((value - min) / width) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            histogram[bin] += 1;
+        }
+        Distribution {
+            name: name.to_string(),
+            count,
+            min,
+            max,
+            mean,
+            percentiles,
+            histogram,
+        }
+    }
+
+    /// One line per bin with its range, count and a bar scaled to the fullest bin.
+    pub fn text_histogram(&self, bar_width: usize) -> String {
+        let largest = self.histogram.iter().copied().max().unwrap_or(0).max(1);
+        let width = (self.max - self.min) / self.histogram.len() as f32;
+        self.histogram
+            .iter()
+            .enumerate()
+            .map(|(bin, count)| {
+                let start = self.min + width * bin as f32;
+                let bar = "█".repeat((count * bar_width).div_ceil(largest));
+                format!(
+                    "{:>12.5} .. {:<12.5} {:>9} {}\n",
+                    start,
+                    start + width,
+                    count,
+                    bar
+                )
+            })
+            .collect()
+    }
+}
+
+/// Counts of splats that are likely to render incorrectly or not at all.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DegenerateCounts {
+    /// NaN or infinite position, color, opacity, scale or rotation.
+    pub non_finite: usize,
+    /// Rotation quaternion with (near) zero norm.
+    pub zero_rotation: usize,
+    /// A linear scale of zero or less on any axis.
+    pub zero_scale: usize,
+    /// Linear opacity below 1/255, which the renderer skips.
+    pub transparent: usize,
+    /// Ratio of largest to smallest scale above 100.
+    pub needle: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SceneStatistics {
+    pub count: usize,
+    pub min_position: Vector3<f32>,
+    pub max_position: Vector3<f32>,
+    pub center: Vector3<f32>,
+    pub distributions: Vec<Distribution>,
+    pub degenerate: DegenerateCounts,
+}
+
+/// Sum of squared coefficients of one SH band over all three channels.
+fn sh_band_energy(rest: &[f32], band: usize) -> Option<f32> {
+    let count = rest.len() / 3;
+    let (start, end) = (band * band - 1, (band + 1) * (band + 1) - 1);
+    if end > count {
+        return None;
+    }
+    Some(
+        (0..3)
+            .flat_map(|channel| &rest[channel * count + start..channel * count + end])
+            .map(|v| v * v)
+            .sum(),
+    )
+}
+
+pub fn scene_statistics(splats: &[UberSplat], bins: usize) -> SceneStatistics {
+    let (min_position, max_position) = find_bounds(splats);
+    let center = find_center(splats);
+    let scales = splats
+        .iter()
+        .map(|s| s.scale.to_linear_float())
+        .collect::<Vec<_>>();
+    let colors = splats
+        .iter()
+        .map(|s| s.color.to_linear_float())
+        .collect::<Vec<_>>();
+    let opacities = splats
+        .iter()
+        .map(|s| s.opacity.to_linear_float())
+        .collect::<Vec<_>>();
+    let anisotropy = scales.iter().map(|s| s.max() / s.min()).collect::<Vec<_>>();
+
+    let mut distributions = vec![
+        Distribution::new("opacity", opacities.iter().copied(), bins),
+        Distribution::new("scale x", scales.iter().map(|s| s.x), bins),
+        Distribution::new("scale y", scales.iter().map(|s| s.y), bins),
+        Distribution::new("scale z", scales.iter().map(|s| s.z), bins),
+        Distribution::new("anisotropy", anisotropy.iter().copied(), bins),
+        Distribution::new("color r", colors.iter().map(|c| c.x), bins),
+        Distribution::new("color g", colors.iter().map(|c| c.y), bins),
+        Distribution::new("color b", colors.iter().map(|c| c.z), bins),
+    ];
+    for band in 1..=MAX_SH_DEGREE {
+        let energies = splats
+            .iter()
+            .filter_map(|s| match &s.color {
+                Color::SphericalHarmonic(_, rest) => sh_band_energy(rest, band),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if !energies.is_empty() {
+            let name = format!("sh band {} energy", band);
+            distributions.push(Distribution::new(&name, energies, bins));
+        }
+    }
+    distributions.extend([
+        Distribution::new(
+            "distance from center",
+            splats.iter().map(|s| (s.position - center).norm()),
+            bins,
+        ),
+        Distribution::new(
+            "quaternion norm",
+            splats.iter().map(|s| s.rotation.norm()),
+            bins,
+        ),
+    ]);
+
+    let mut degenerate = DegenerateCounts::default();
+    for (index, splat) in splats.iter().enumerate() {
+        let finite = splat.position.iter().all(|v| v.is_finite())
+            && colors[index].iter().all(|v| v.is_finite())
+            && opacities[index].is_finite()
+            && scales[index].iter().all(|v| v.is_finite())
+            && splat.rotation.coords.iter().all(|v| v.is_finite());
+        degenerate.non_finite += !finite as usize;
+        degenerate.zero_rotation += (splat.rotation.norm() < 1e-8) as usize;
+        degenerate.zero_scale += scales[index].iter().any(|v| *v <= 0.0) as usize;
+        degenerate.transparent += (opacities[index] < 1.0 / 255.0) as usize;
+        degenerate.needle += (anisotropy[index] > 100.0) as usize;
+    }
+
+    SceneStatistics {
+        count: splats.len(),
+        min_position,
+        max_position,
+        center,
+        distributions,
+        degenerate,
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_distribution_percentiles_and_histogram() {
+    let distribution = Distribution::new("test", (0..=100).map(|v| v as f32), 4);
+    assert_eq!(distribution.percentiles[3], 50.0);
+    assert_eq!(distribution.percentiles[6], 99.0);
+    assert_eq!(distribution.mean, 50.0);
+    assert_eq!(distribution.histogram, vec![25, 25, 25, 26]);
+    let empty = Distribution::new("empty", [f32::NAN], 4);
+    assert_eq!(empty.count, 0);
+}
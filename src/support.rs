@@ -1,24 +1,48 @@
-use byteorder::{LittleEndian, ReadBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use half::f16;
 use nalgebra::{Quaternion, Vector3, Vector4};
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
+
+/// Values reserved before reading, so a corrupt count fails when the data runs out instead of allocating for it.
+const MAX_RESERVED: usize = 1 << 16;
+
+/// Read `count` values with `read_value`, which is given each value's index.
+pub fn read_values<R: Read, T>(
+    reader: &mut R,
+    count: usize,
+    mut read_value: impl FnMut(&mut R, usize) -> io::Result<T>,
+) -> io::Result<Vec<T>> {
+    let mut values = Vec::with_capacity(count.min(MAX_RESERVED));
+    for index in 0..count {
+        values.push(read_value(reader, index)?);
+    }
+    Ok(values)
+}
+
+pub fn read_f16<R: Read>(reader: &mut R) -> io::Result<f16> {
+    Ok(f16::from_bits(reader.read_u16::<LittleEndian>()?))
+}
+
+pub fn write_f16<W: Write>(writer: &mut W, value: f16) -> io::Result<()> {
+    writer.write_u16::<LittleEndian>(value.to_bits())
+}
 
 #[allow(dead_code)]
 pub fn read_vector3_f16<R: Read>(reader: &mut R) -> io::Result<Vector3<f16>> {
     Ok(Vector3::new(
-        f16::from_bits(reader.read_u16::<LittleEndian>().unwrap()),
-        f16::from_bits(reader.read_u16::<LittleEndian>().unwrap()),
-        f16::from_bits(reader.read_u16::<LittleEndian>().unwrap()),
+        read_f16(reader)?,
+        read_f16(reader)?,
+        read_f16(reader)?,
     ))
 }
 
 #[allow(dead_code)]
 pub fn read_vector4_f16<R: Read>(reader: &mut R) -> io::Result<Vector4<f16>> {
     Ok(Vector4::new(
-        f16::from_bits(reader.read_u16::<LittleEndian>().unwrap()),
-        f16::from_bits(reader.read_u16::<LittleEndian>().unwrap()),
-        f16::from_bits(reader.read_u16::<LittleEndian>().unwrap()),
-        f16::from_bits(reader.read_u16::<LittleEndian>().unwrap()),
+        read_f16(reader)?,
+        read_f16(reader)?,
+        read_f16(reader)?,
+        read_f16(reader)?,
     ))
 }
 
@@ -85,7 +109,33 @@ pub fn color_and_alpha_from_u8(color: Vector4<u8>) -> (Vector3<f32>, f32) {
     (color, alpha)
 }
 
-#[allow(dead_code)]
-pub fn srgb_to_linear(rgb: Vector3<f32>) -> Vector3<f32> {
-    rgb.map(|v| v.powf(2.2))
+/// The piecewise sRGB transfer function, from an encoded value to linear light.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// The inverse of `srgb_to_linear`, from linear light to an encoded value.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Parse a comma separated `x,y,z` command line value.
+pub fn parse_vector3(s: &str) -> Result<Vector3<f32>, String> {
+    let values = s
+        .split(',')
+        .map(|v| v.trim().parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|e| format!("Invalid number in `{}`: {}", s, e))?;
+    match values.as_slice() {
+        [x, y, z] => Ok(Vector3::new(*x, *y, *z)),
+        _ => Err(format!("Expected `x,y,z`, got `{}`", s)),
+    }
 }
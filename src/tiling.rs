@@ -0,0 +1,137 @@
+use crate::*;
+use anyhow::Result;
+use clap::ValueEnum;
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The plane that the tile grid is laid out on.
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+pub enum TilePlane {
+    XY,
+    XZ,
+}
+
+impl TilePlane {
+    fn coordinates(&self, position: &Vector3<f32>) -> (f32, f32) {
+        match self {
+            TilePlane::XY => (position.x, position.y),
+            TilePlane::XZ => (position.x, position.z),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileEntry {
+    pub file: String,
+    pub column: i64,
+    pub row: i64,
+    pub count: usize,
+    /// Bounds of the splat positions inside this tile.
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileManifest {
+    pub plane: TilePlane,
+    pub tile_size: f32,
+    pub count: usize,
+    pub tiles: Vec<TileEntry>,
+}
+
+/// Bucket splats into a regular grid of `tile_size` cells aligned to the world origin, keyed by (column, row).
+pub fn tile_splats(
+    splats: Vec<UberSplat>,
+    plane: TilePlane,
+    tile_size: f32,
+) -> BTreeMap<(i64, i64), Vec<UberSplat>> {
+    let mut tiles: BTreeMap<(i64, i64), Vec<UberSplat>> = BTreeMap::new();
+    for splat in splats {
+        let (u, v) = plane.coordinates(&splat.position);
+        let key = (
+            (u / tile_size).floor() as i64,
+            (v / tile_size).floor() as i64,
+        );
+        tiles.entry(key).or_default().push(splat);
+    }
+    tiles
+}
+
+/// Split `input` into tiles written to `directory` alongside a `manifest.json`.
+pub fn save_tiles(
+    input: &Path,
+    directory: &Path,
+    plane: TilePlane,
+    tile_size: f32,
+    extension: &str,
+) -> Result<TileManifest> {
+    if !(tile_size > 0.0 && tile_size.is_finite()) {
+        anyhow::bail!("Tile size must be positive");
+    }
+    let Some(format) = guess_format(&directory.join(format!("tile.{}", extension))) else {
+        anyhow::bail!("Unknown tile format {}", extension);
+    };
+    let splats = load_splats(input)?;
+    let count = splats.len();
+    std::fs::create_dir_all(directory)?;
+
+    let mut entries = Vec::new();
+    for ((column, row), splats) in tile_splats(splats, plane, tile_size) {
+        let file = format!("tile_{}_{}.{}", column, row, extension);
+        let path = directory.join(&file);
+        let (min, max) = find_bounds(&splats);
+        let entry = TileEntry {
+            file,
+            column,
+            row,
+            count: splats.len(),
+            min,
+            max,
+        };
+        save_splats(splats, format, &path)?;
+        entries.push(entry);
+    }
+
+    let manifest = TileManifest {
+        plane,
+        tile_size,
+        count,
+        tiles: entries,
+    };
+    let json = serde_json::to_string_pretty(&manifest)?;
+    std::fs::write(directory.join("manifest.json"), json)?;
+    Ok(manifest)
+}
+
+/// Load every tile listed in a manifest and concatenate them back into one scene.
+pub fn load_tiles(manifest_path: &Path) -> Result<Vec<UberSplat>> {
+    let json = std::fs::read_to_string(manifest_path)?;
+    let manifest: TileManifest = serde_json::from_str(&json)?;
+    let directory = manifest_path.parent().unwrap_or(Path::new("."));
+    let mut splats = Vec::with_capacity(manifest.count);
+    for tile in &manifest.tiles {
+        let tile_splats = load_splats(&directory.join(&tile.file))?;
+        if tile_splats.len() != tile.count {
+            anyhow::bail!(
+                "Tile {} has {} splats, manifest says {}",
+                tile.file,
+                tile_splats.len(),
+                tile.count
+            );
+        }
+        splats.extend(tile_splats);
+    }
+    Ok(splats)
+}
+
+#[cfg(test)]
+#[test]
+fn test_tile_splats_negative_coordinates() {
+    let splat = |x: f32, y: f32| UberSplat::test_splat(Vector3::new(x, y, 0.0));
+    let splats = vec![splat(0.5, 0.5), splat(-0.5, 0.5), splat(1.5, -2.5)];
+    let tiles = tile_splats(splats, TilePlane::XY, 1.0);
+    let keys = tiles.keys().cloned().collect::<Vec<_>>();
+    assert_eq!(keys, vec![(-1, 0), (0, 0), (1, -3)]);
+}
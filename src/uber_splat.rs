@@ -1,9 +1,13 @@
+use crate::*;
 use derive_new::new as New;
 use nalgebra::{Matrix3, Quaternion, UnitQuaternion, Vector3};
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display, Formatter};
 use tabled::Tabled;
 
+/// The colors of every variant are sRGB encoded, like the images 3DGS is trained on and the color it displays from
+/// spherical harmonics. `LinearFloat` and `LinearU8` are plain colors in [0, 1] as floats or bytes, as opposed to
+/// spherical harmonics; formats that store linear light convert with `ColorSpace` when loading and saving.
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Color {
@@ -27,20 +31,11 @@ impl Display for Color {
 impl Color {
     pub fn to_linear_float(&self) -> Vector3<f32> {
         match self {
-            Color::SphericalHarmonic(rgb, _) => {
-                let sh_c0: f32 = 0.282_094_8;
+            Color::SphericalHarmonic(rgb, _) | Color::FirstOrderSphericalHarmonic(rgb) => {
                 Vector3::new(
-                    (0.5 + sh_c0 * rgb[0]).clamp(0.0, 1.0),
-                    (0.5 + sh_c0 * rgb[1]).clamp(0.0, 1.0),
-                    (0.5 + sh_c0 * rgb[2]).clamp(0.0, 1.0),
-                )
-            }
-            Color::FirstOrderSphericalHarmonic(rgb) => {
-                let sh_c0: f32 = 0.282_094_8;
-                Vector3::new(
-                    (0.5 + sh_c0 * rgb[0]).clamp(0.0, 1.0),
-                    (0.5 + sh_c0 * rgb[1]).clamp(0.0, 1.0),
-                    (0.5 + sh_c0 * rgb[2]).clamp(0.0, 1.0),
+                    (0.5 + SH_C0 * rgb[0]).clamp(0.0, 1.0),
+                    (0.5 + SH_C0 * rgb[1]).clamp(0.0, 1.0),
+                    (0.5 + SH_C0 * rgb[2]).clamp(0.0, 1.0),
                 )
             }
             Color::LinearFloat(v) => *v,
@@ -53,12 +48,41 @@ impl Color {
     }
 
     pub fn to_linear_u8(&self) -> Vector3<u8> {
-        let f = self.to_linear_float();
-        Vector3::new(
-            (f[0] * 255.0) as u8,
-            (f[1] * 255.0) as u8,
-            (f[2] * 255.0) as u8,
-        )
+        self.to_linear_float().map(unit_to_u8)
+    }
+
+    /// The color seen looking along `direction`, taking view dependent spherical harmonics into account.
+    pub fn evaluate(&self, direction: &Vector3<f32>) -> Vector3<f32> {
+        match self {
+            Color::SphericalHarmonic(dc, rest) => evaluate_sh(dc, rest, direction),
+            _ => self.to_linear_float(),
+        }
+    }
+
+    /// The spherical harmonic degree of the color, or `None` if it is not stored as spherical harmonics.
+    pub fn sh_degree(&self) -> Option<usize> {
+        match self {
+            Color::SphericalHarmonic(_, rest) => sh_degree_for_rest_len(rest.len()),
+            Color::FirstOrderSphericalHarmonic(_) => Some(0),
+            _ => None,
+        }
+    }
+
+    /// Re-encode the color as spherical harmonics of the given degree, truncating or zero-padding higher bands.
+    pub fn to_spherical_harmonic(&self, degree: usize) -> Color {
+        let (dc, rest) = match self {
+            Color::SphericalHarmonic(dc, rest) => (*dc, resize_sh_rest(rest, degree)),
+            Color::FirstOrderSphericalHarmonic(dc) => (*dc, resize_sh_rest(&[], degree)),
+            color => (
+                color.to_linear_float().map(|v| (v - 0.5) / SH_C0),
+                resize_sh_rest(&[], degree),
+            ),
+        };
+        if degree == 0 {
+            Color::FirstOrderSphericalHarmonic(dc)
+        } else {
+            Color::SphericalHarmonic(dc, rest)
+        }
     }
 }
 
@@ -93,11 +117,51 @@ impl Opacity {
     }
 
     pub fn to_linear_u8(&self) -> u8 {
-        let f = self.to_linear_float();
-        (f * 255.0) as u8
+        unit_to_u8(self.to_linear_float())
+    }
+}
+
+/// How a format encodes plain colors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorSpace {
+    /// Encoded with the piecewise sRGB transfer function, as `Color` holds them.
+    Srgb,
+    /// Linear light.
+    Linear,
+}
+
+impl Display for ColorSpace {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ColorSpace::Srgb => write!(f, "sRGB"),
+            ColorSpace::Linear => write!(f, "linear"),
+        }
+    }
+}
+
+impl ColorSpace {
+    /// Convert an sRGB encoded color, as held by `Color`, to this space.
+    pub fn encode(&self, rgb: Vector3<f32>) -> Vector3<f32> {
+        match self {
+            ColorSpace::Srgb => rgb,
+            ColorSpace::Linear => rgb.map(srgb_to_linear),
+        }
+    }
+
+    /// Convert a color in this space to sRGB encoding, as held by `Color`.
+    pub fn decode(&self, rgb: Vector3<f32>) -> Vector3<f32> {
+        match self {
+            ColorSpace::Srgb => rgb,
+            ColorSpace::Linear => rgb.map(linear_to_srgb),
+        }
     }
 }
 
+/// Quantise a value in [0, 1] to a byte, rounding half to even and clamping like a JavaScript `Uint8ClampedArray`.
+pub fn unit_to_u8(value: f32) -> u8 {
+    (value * 255.0).round_ties_even().clamp(0.0, 255.0) as u8
+}
+
 fn exp(value: f32) -> f32 {
     value.exp()
 }
@@ -119,6 +183,14 @@ impl Display for Scale {
 }
 
 impl Scale {
+    /// A scale with the same representation as this one, from linear values.
+    pub fn with_linear(&self, linear: Vector3<f32>) -> Scale {
+        match self {
+            Scale::Exponent(_) => Scale::Exponent(linear.map(f32::ln)),
+            Scale::LinearFloat(_) => Scale::LinearFloat(linear),
+        }
+    }
+
     pub fn to_linear_float(&self) -> Vector3<f32> {
         match self {
             Scale::Exponent(value) => Vector3::new(value[0].exp(), value[1].exp(), value[2].exp()),
@@ -131,7 +203,6 @@ impl Scale {
 
 #[derive(Debug, Clone, New, PartialEq, Serialize, Deserialize, Tabled)]
 #[tabled(rename_all = "CamelCase")]
-
 pub struct UberSplat {
     pub position: Vector3<f32>,
     #[tabled(display_with = "display_option")]
@@ -142,6 +213,21 @@ pub struct UberSplat {
     pub rotation: Quaternion<f32>,
 }
 
+#[cfg(test)]
+impl UberSplat {
+    /// A grey, half opaque splat with a linear scale of 0.1 at `position`, for tests.
+    pub fn test_splat(position: Vector3<f32>) -> UberSplat {
+        UberSplat::new(
+            position,
+            None,
+            Color::LinearFloat(Vector3::repeat(0.5)),
+            Opacity::LinearFloat(0.5),
+            Scale::LinearFloat(Vector3::repeat(0.1)),
+            Quaternion::identity(),
+        )
+    }
+}
+
 impl UberSplat {
     pub fn to_cov(&self) -> (Vector3<f32>, Vector3<f32>) {
         let rotation = UnitQuaternion::from_quaternion(self.rotation).to_rotation_matrix();
@@ -152,6 +238,53 @@ impl UberSplat {
         let cov_b = Vector3::new(cov3d[(1, 1)], cov3d[(1, 2)], cov3d[(2, 2)]);
         (cov_a, cov_b)
     }
+
+    /// The rotation scaled to unit length, or the identity for a zero quaternion.
+    pub fn unit_rotation(&self) -> Quaternion<f32> {
+        let norm = self.rotation.norm();
+        if norm > 0.0 {
+            self.rotation / norm
+        } else {
+            Quaternion::identity()
+        }
+    }
+
+    /// True if every stored value, in its stored representation, is finite.
+    pub fn is_finite(&self) -> bool {
+        let finite = |v: &f32| v.is_finite();
+        let color = match &self.color {
+            Color::SphericalHarmonic(dc, rest) => dc.iter().chain(rest).all(finite),
+            Color::FirstOrderSphericalHarmonic(dc) | Color::LinearFloat(dc) => {
+                dc.iter().all(finite)
+            }
+            Color::LinearU8(_) => true,
+        };
+        let opacity = match self.opacity {
+            Opacity::LinearFloat(v) | Opacity::LogitFloat(v) => v.is_finite(),
+            Opacity::LinearU8(_) => true,
+        };
+        let scale = match &self.scale {
+            Scale::Exponent(v) | Scale::LinearFloat(v) => v.iter().all(finite),
+        };
+        self.position.iter().all(finite)
+            && self.normal.is_none_or(|n| n.iter().all(finite))
+            && color
+            && opacity
+            && scale
+            && self.rotation.coords.iter().all(finite)
+    }
+
+    /// The stored normal if there is a non-zero one, otherwise the gaussian's shortest axis.
+    pub fn surface_normal(&self) -> Vector3<f32> {
+        if let Some(normal) = self.normal {
+            if normal.norm_squared() > 0.0 {
+                return normal.normalize();
+            }
+        }
+        let rotation = UnitQuaternion::from_quaternion(self.rotation).to_rotation_matrix();
+        let scale = self.scale.to_linear_float();
+        rotation.matrix().column(scale.imin()).into_owned()
+    }
 }
 
 fn display_option(o: &Option<Vector3<f32>>) -> String {
@@ -160,3 +293,15 @@ fn display_option(o: &Option<Vector3<f32>>) -> String {
         None => "-".to_string(),
     }
 }
+
+#[cfg(test)]
+#[test]
+fn test_color_space_conversions() {
+    // Mid grey, the linear segment near black and the end points of the piecewise sRGB curve.
+    let srgb = Vector3::new(0.5, 0.02, 1.0);
+    let linear = ColorSpace::Linear.encode(srgb);
+    assert!((linear - Vector3::new(0.214_041_14, 0.001_547_988, 1.0)).norm() < 1e-6);
+    assert!((ColorSpace::Linear.decode(linear) - srgb).norm() < 1e-6);
+    assert_eq!(ColorSpace::Srgb.encode(srgb), srgb);
+    assert_eq!(srgb_to_linear(0.0), 0.0);
+}
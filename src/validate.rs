@@ -0,0 +1,416 @@
+use crate::*;
+use ply_rs::parser::Parser;
+use ply_rs::ply::{DefaultElement, Encoding, PropertyType, ScalarType};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read};
+use std::path::Path;
+
+/// Number of example indices kept for each issue.
+const MAX_EXAMPLES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// One kind of problem, with how many splats have it and the first few of their indices.
+#[derive(Debug, Clone, Serialize)]
+pub struct Issue {
+    pub severity: Severity,
+    pub check: String,
+    pub message: String,
+    pub count: usize,
+    pub indices: Vec<usize>,
+}
+
+/// Collects issues, grouping splat level problems by check.
+#[derive(Debug, Default)]
+pub struct Validation {
+    issues: Vec<Issue>,
+}
+
+impl Validation {
+    /// A file level issue.
+    pub fn report(&mut self, severity: Severity, check: &str, message: String) {
+        self.issues.push(Issue {
+            severity,
+            check: check.to_string(),
+            message,
+            count: 1,
+            indices: Vec::new(),
+        });
+    }
+
+    /// A problem with the splat at `index`. The message of the first occurrence is kept.
+    pub fn report_splat(&mut self, severity: Severity, check: &str, index: usize, message: &str) {
+        let existing = self
+            .issues
+            .iter_mut()
+            .find(|issue| issue.check == check && issue.severity == severity);
+        match existing {
+            Some(issue) => {
+                issue.count += 1;
+                if issue.indices.len() < MAX_EXAMPLES {
+                    issue.indices.push(index);
+                }
+            }
+            None => self.issues.push(Issue {
+                severity,
+                check: check.to_string(),
+                message: message.to_string(),
+                count: 1,
+                indices: vec![index],
+            }),
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|i| i.severity == Severity::Error)
+    }
+
+    /// Issues with errors first.
+    pub fn into_issues(mut self) -> Vec<Issue> {
+        self.issues
+            .sort_by_key(|issue| std::cmp::Reverse(issue.severity));
+        self.issues
+    }
+}
+
+fn scalar_size(scalar: &ScalarType) -> u64 {
+    match scalar {
+        ScalarType::Char | ScalarType::UChar => 1,
+        ScalarType::Short | ScalarType::UShort => 2,
+        ScalarType::Int | ScalarType::UInt | ScalarType::Float => 4,
+        ScalarType::Double => 8,
+    }
+}
+
+/// A reader that counts the bytes taken from it, to find where the PLY header ends without buffering the file.
+struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: BufRead> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for CountingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount);
+        self.count += amount as u64;
+    }
+}
+
+/// Check a PLY header against the 3DGS layout and the file size. Returns false when the file can not be loaded.
+fn validate_ply_header(path: &Path, validation: &mut Validation) -> bool {
+    let (Ok(input), Ok(size)) = (open_input(path), input_size(path)) else {
+        validation.report(Severity::Error, "file", "Can not read file".to_string());
+        return false;
+    };
+    let mut reader = CountingReader {
+        inner: input,
+        count: 0,
+    };
+    let header = match Parser::<DefaultElement>::new().read_header(&mut reader) {
+        Ok(header) => header,
+        Err(error) => {
+            let message = format!("Malformed PLY header: {}", error);
+            validation.report(Severity::Error, "ply header", message);
+            return false;
+        }
+    };
+    let Some(vertex) = header.elements.get("vertex") else {
+        let message = "No vertex element".to_string();
+        validation.report(Severity::Error, "ply header", message);
+        return false;
+    };
+    let mut loadable = true;
+    if header.elements.len() != 1 {
+        let names = header.elements.keys().cloned().collect::<Vec<_>>();
+        let message = format!("Expected only a vertex element, got {}", names.join(", "));
+        validation.report(Severity::Error, "ply header", message);
+        loadable = false;
+    }
+
+    let required = [
+        "x", "y", "z", "nx", "ny", "nz", "f_dc_0", "f_dc_1", "f_dc_2", "opacity", "scale_0",
+        "scale_1", "scale_2", "rot_0", "rot_1", "rot_2", "rot_3",
+    ];
+    for name in required {
+        match vertex.properties.get(name).map(|p| &p.data_type) {
+            Some(PropertyType::Scalar(ScalarType::Float)) => (),
+            Some(data_type) => {
+                let message = format!("Property {} is {:?}, expected float", name, data_type);
+                validation.report(Severity::Error, "ply header", message);
+                loadable = false;
+            }
+            // 2DGS surfels have no third scale.
+            None if name == "scale_2" => (),
+            None => {
+                let message = format!("Missing property {}", name);
+                validation.report(Severity::Error, "ply header", message);
+                loadable = false;
+            }
+        }
+    }
+    for property in vertex.properties.values() {
+        let name = &property.name;
+        if name.starts_with("f_rest_") {
+            if property.data_type != PropertyType::Scalar(ScalarType::Float) {
+                let message = format!(
+                    "Property {} is {:?}, expected float",
+                    name, property.data_type
+                );
+                validation.report(Severity::Error, "ply header", message);
+                loadable = false;
+            }
+        } else if !required.contains(&name.as_str()) {
+            let message = format!("Property {} is not a 3DGS property and is ignored", name);
+            validation.report(Severity::Warning, "ply header", message);
+        }
+    }
+    let rest_count = vertex
+        .properties
+        .keys()
+        .filter(|name| name.starts_with("f_rest_"))
+        .count();
+    let contiguous =
+        (0..rest_count).all(|i| vertex.properties.contains_key(&format!("f_rest_{}", i)));
+    match sh_degree_for_rest_len(rest_count) {
+        Some(_) if !contiguous => {
+            let message = "f_rest properties are not numbered 0..n".to_string();
+            validation.report(Severity::Error, "spherical harmonics", message);
+            loadable = false;
+        }
+        Some(_) => (),
+        None => {
+            let message = format!(
+                "{} f_rest properties do not match any SH degree",
+                rest_count
+            );
+            validation.report(Severity::Error, "spherical harmonics", message);
+            loadable = false;
+        }
+    }
+
+    if header.encoding != Encoding::Ascii {
+        let mut stride = 0;
+        for property in vertex.properties.values() {
+            match &property.data_type {
+                PropertyType::Scalar(scalar) => stride += scalar_size(scalar),
+                PropertyType::List(..) => {
+                    let message = format!("List property {} is not supported", property.name);
+                    validation.report(Severity::Error, "ply header", message);
+                    return false;
+                }
+            }
+        }
+        let expected = reader.count + stride * vertex.count as u64;
+        if size != expected {
+            let message = format!(
+                "Header declares {} splats ({} bytes) but the file is {} bytes",
+                vertex.count, expected, size
+            );
+            let severity = if size < expected {
+                loadable = false;
+                Severity::Error
+            } else {
+                Severity::Warning
+            };
+            validation.report(severity, "record count", message);
+        }
+    }
+    loadable
+}
+
+/// Check that the file size is a whole number of fixed size records.
+fn validate_record_size(path: &Path, record_size: u64, validation: &mut Validation) {
+    let size = input_size(path).unwrap_or(0);
+    if !size.is_multiple_of(record_size) {
+        let message = format!(
+            "{} bytes is not a multiple of the {} byte record size; {} trailing bytes are ignored",
+            size,
+            record_size,
+            size % record_size
+        );
+        validation.report(Severity::Error, "record count", message);
+    }
+    if size == 0 {
+        validation.report(
+            Severity::Warning,
+            "record count",
+            "File is empty".to_string(),
+        );
+    }
+}
+
+/// Per-splat checks for non-finite values, degenerate rotations and scales, out of range opacities, inconsistent
+/// spherical harmonics and duplicates.
+pub fn validate_splats(splats: &[UberSplat], validation: &mut Validation) {
+    let (min, max) = find_bounds(splats);
+    let extent = (max - min).norm();
+    let sh_degrees = splats
+        .iter()
+        .filter_map(|s| s.color.sh_degree())
+        .collect::<Vec<_>>();
+    let common_degree = sh_degrees.first().copied();
+    let mut positions = HashMap::new();
+
+    for (index, splat) in splats.iter().enumerate() {
+        if !splat.is_finite() {
+            validation.report_splat(
+                Severity::Error,
+                "non-finite",
+                index,
+                "NaN or infinite values",
+            );
+            continue;
+        }
+
+        let norm = splat.rotation.norm();
+        if norm < 1e-8 {
+            validation.report_splat(Severity::Error, "rotation", index, "Zero length quaternion");
+        } else if (norm - 1.0).abs() > 0.01 {
+            let message = "Quaternion is not normalised; it is normalised when rendered";
+            validation.report_splat(Severity::Warning, "rotation norm", index, message);
+        }
+
+        let linear_scale = splat.scale.to_linear_float();
+        if linear_scale.iter().any(|v| *v <= 0.0) {
+            let message = "Scale is zero or negative";
+            validation.report_splat(Severity::Error, "scale", index, message);
+        } else if linear_scale.min() < 1e-7 || linear_scale.max() > extent.max(1.0) {
+            let message = "Scale is below 1e-7 or larger than the whole scene";
+            validation.report_splat(Severity::Warning, "scale range", index, message);
+        }
+
+        if let Opacity::LinearFloat(value) = splat.opacity {
+            if !(0.0..=1.0).contains(&value) {
+                let message = "Linear opacity outside [0, 1]";
+                validation.report_splat(Severity::Error, "opacity", index, message);
+            }
+        }
+
+        if let Color::SphericalHarmonic(_, rest) = &splat.color {
+            match sh_degree_for_rest_len(rest.len()) {
+                None => {
+                    let message = "SH coefficient count does not match any degree";
+                    validation.report_splat(Severity::Error, "spherical harmonics", index, message);
+                }
+                Some(degree) if Some(degree) != common_degree => {
+                    let message = "SH degree differs from the first splat";
+                    validation.report_splat(Severity::Warning, "mixed sh degree", index, message);
+                }
+                _ => (),
+            }
+        }
+
+        let key = splat.position.map(f32::to_bits);
+        match positions.get(&key) {
+            Some(first) if splats[*first] == *splat => {
+                let message = "Exact duplicate of an earlier splat";
+                validation.report_splat(Severity::Warning, "duplicate", index, message);
+            }
+            Some(_) => {
+                let message = "Same position as an earlier splat";
+                validation.report_splat(Severity::Warning, "coincident", index, message);
+            }
+            None => {
+                positions.insert(key, index);
+            }
+        }
+    }
+}
+
+/// Run every check that applies to the file's format.
+pub fn validate_file(path: &Path) -> Validation {
+    let mut validation = Validation::default();
+    if !path.exists() {
+        validation.report(Severity::Error, "file", "File does not exist".to_string());
+        return validation;
+    }
+    // Check the container first, since format detection assumes a well formed file.
+    let inner = inner_path(path);
+    let extension = inner
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    match extension {
+        "ply" if !validate_ply_header(path, &mut validation) => return validation,
+        "splat" => validate_record_size(path, 32, &mut validation),
+        "splatc" => validate_record_size(path, 26, &mut validation),
+        _ => (),
+    }
+    if validation.has_errors() {
+        return validation;
+    }
+    if guess_format(path).is_none() {
+        validation.report(Severity::Error, "format", "Unknown format".to_string());
+        return validation;
+    }
+    match load_splats(path) {
+        Ok(splats) => validate_splats(&splats, &mut validation),
+        Err(error) => validation.report(Severity::Error, "load", error.to_string()),
+    }
+    validation
+}
+
+#[cfg(test)]
+#[test]
+fn test_validate_splats_reports_indices() {
+    let mut splats = (0..5)
+        .map(|i| UberSplat::test_splat(nalgebra::Vector3::new(i as f32, 0.0, 0.0)))
+        .collect::<Vec<_>>();
+    splats[1].rotation = nalgebra::Quaternion::new(0.0, 0.0, 0.0, 0.0);
+    splats[2].opacity = Opacity::LinearFloat(1.5);
+    splats[3].position.y = f32::NAN;
+    splats.push(splats[4].clone());
+
+    let mut validation = Validation::default();
+    validate_splats(&splats, &mut validation);
+    assert!(validation.has_errors());
+    let issues = validation.into_issues();
+    let find = |check: &str| issues.iter().find(|i| i.check == check).unwrap();
+    assert_eq!(find("rotation").indices, vec![1]);
+    assert_eq!(find("opacity").indices, vec![2]);
+    assert_eq!(find("non-finite").indices, vec![3]);
+    assert_eq!(find("duplicate").indices, vec![5]);
+    assert_eq!(issues.len(), 4);
+}
+
+#[cfg(test)]
+#[test]
+fn test_validate_ply_with_extra_property() {
+    let names = [
+        "x", "y", "z", "nx", "ny", "nz", "f_dc_0", "f_dc_1", "f_dc_2", "opacity", "scale_0",
+        "scale_1", "scale_2", "rot_0", "rot_1", "rot_2", "rot_3",
+    ];
+    let ply = format!(
+        "ply\nformat ascii 1.0\nelement vertex 1\n{}property uchar red\nend_header\n{}\n",
+        names
+            .map(|name| format!("property float {}\n", name))
+            .concat(),
+        "0 0 0 0 0 0 0 0 0 0 -1 -1 -1 1 0 0 0 255"
+    );
+    let path = std::env::temp_dir().join(format!("extra-property-{}.ply", std::process::id()));
+    std::fs::write(&path, ply).unwrap();
+    let validation = validate_file(&path);
+    let loaded = load_splats(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(!validation.has_errors());
+    let issues = validation.into_issues();
+    assert!(issues.iter().any(|i| i.message.contains("red")));
+    assert_eq!(loaded[0].rotation, nalgebra::Quaternion::identity());
+}
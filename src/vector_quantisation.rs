@@ -0,0 +1,204 @@
+use crate::*;
+use anyhow::Result;
+use nalgebra::{Quaternion, Vector3};
+
+#[derive(Debug, Clone)]
+pub struct VqOptions {
+    /// Entries in the codebook for the higher spherical harmonic bands.
+    pub sh_codebook: usize,
+    /// Entries in the codebook for log scales, or `None` to store them as floats.
+    pub scale_codebook: Option<usize>,
+    /// Entries in the codebook for rotations, or `None` to store them as floats.
+    pub rotation_codebook: Option<usize>,
+    /// Rounds of Lloyd's algorithm.
+    pub iterations: usize,
+    pub seed: Option<u64>,
+}
+
+impl Default for VqOptions {
+    fn default() -> Self {
+        VqOptions {
+            sh_codebook: 4096,
+            scale_codebook: None,
+            rotation_codebook: None,
+            iterations: 10,
+            seed: None,
+        }
+    }
+}
+
+/// A per-splat attribute, stored either as `dimension` floats per splat or as an index into a codebook.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VqAttribute {
+    Raw(Vec<f32>),
+    Quantised(Codebook, Vec<u32>),
+}
+
+impl VqAttribute {
+    fn new(
+        values: Vec<f32>,
+        dimension: usize,
+        codebook: Option<usize>,
+        options: &VqOptions,
+        rng: &mut SplatRng,
+    ) -> VqAttribute {
+        match codebook {
+            Some(size) => {
+                let codebook = train_codebook(&values, dimension, size, options.iterations, rng);
+                let indices = codebook.assign(&values);
+                VqAttribute::Quantised(codebook, indices)
+            }
+            None => VqAttribute::Raw(values),
+        }
+    }
+
+    pub fn value(&self, index: usize, dimension: usize) -> &[f32] {
+        match self {
+            VqAttribute::Raw(values) => &values[index * dimension..(index + 1) * dimension],
+            VqAttribute::Quantised(codebook, indices) => codebook.entry(indices[index] as usize),
+        }
+    }
+}
+
+/// A scene with its higher spherical harmonic bands, and optionally its scales and rotations, replaced by indices
+/// into k-means codebooks, as in CompactGS and LightGaussian. Other attributes are kept as the 3DGS parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VqScene {
+    pub positions: Vec<Vector3<f32>>,
+    pub dc: Vec<Vector3<f32>>,
+    /// Opacities before the sigmoid.
+    pub opacities: Vec<f32>,
+    /// Log scales, 3 floats per splat.
+    pub scales: VqAttribute,
+    /// Normalised `w, x, y, z` quaternions with `w >= 0`, 4 floats per splat.
+    pub rotations: VqAttribute,
+    /// Degree of the spherical harmonics, with a codebook of `f_rest` vectors when it is above zero.
+    pub sh_degree: usize,
+    pub sh: Option<(Codebook, Vec<u32>)>,
+}
+
+impl VqScene {
+    pub fn encode(splats: &[UberSplat], options: &VqOptions) -> Result<VqScene> {
+        if let Some(index) = splats.iter().position(|s| !s.is_finite()) {
+            anyhow::bail!(
+                "Splat {} has NaN or infinite values; run `repair` first",
+                index
+            );
+        }
+        let mut rng = splat_rng(options.seed);
+        let sh_degree = splats
+            .iter()
+            .filter_map(|s| s.color.sh_degree())
+            .max()
+            .unwrap_or(0);
+        let parameters = splats.iter().map(SplatA::from).collect::<Vec<_>>();
+
+        let scales = parameters
+            .iter()
+            .flat_map(|p| p.scale.iter().copied())
+            .collect();
+        let rotations = splats
+            .iter()
+            .flat_map(|splat| {
+                let rotation = splat.unit_rotation();
+                // q and -q are the same rotation, so pick one to halve the space to cluster.
+                let rotation = if rotation.w < 0.0 {
+                    -rotation
+                } else {
+                    rotation
+                };
+                [rotation.w, rotation.i, rotation.j, rotation.k]
+            })
+            .collect();
+        let sh = (sh_degree > 0).then(|| {
+            let dimension = 3 * (sh_coefficient_count(sh_degree) - 1);
+            let rest = parameters
+                .iter()
+                .flat_map(|p| resize_sh_rest(p.f_rest.as_deref().unwrap_or_default(), sh_degree))
+                .collect::<Vec<f32>>();
+            let codebook = train_codebook(
+                &rest,
+                dimension,
+                options.sh_codebook,
+                options.iterations,
+                &mut rng,
+            );
+            let indices = codebook.assign(&rest);
+            (codebook, indices)
+        });
+
+        Ok(VqScene {
+            positions: parameters.iter().map(|p| p.position).collect(),
+            dc: parameters.iter().map(|p| p.f_dc).collect(),
+            opacities: parameters.iter().map(|p| p.opacity).collect(),
+            scales: VqAttribute::new(scales, 3, options.scale_codebook, options, &mut rng),
+            rotations: VqAttribute::new(rotations, 4, options.rotation_codebook, options, &mut rng),
+            sh_degree,
+            sh,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn decode(&self) -> Vec<UberSplat> {
+        (0..self.len())
+            .map(|index| {
+                let dc = self.dc[index];
+                let color = match &self.sh {
+                    Some((codebook, indices)) => Color::SphericalHarmonic(
+                        dc,
+                        codebook.entry(indices[index] as usize).to_vec(),
+                    ),
+                    None => Color::FirstOrderSphericalHarmonic(dc),
+                };
+                let scale = Vector3::from_column_slice(self.scales.value(index, 3));
+                let [w, x, y, z] = self.rotations.value(index, 4) else {
+                    unreachable!()
+                };
+                UberSplat::new(
+                    self.positions[index],
+                    None,
+                    color,
+                    Opacity::LogitFloat(self.opacities[index]),
+                    Scale::Exponent(scale),
+                    Quaternion::new(*w, *x, *y, *z),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_vq_scene_round_trip() {
+    let splats = (0..200)
+        .map(|i| {
+            // Two distinct sets of SH coefficients and four scales.
+            let rest = vec![if i % 2 == 0 { 0.1 } else { -0.2 }; 45];
+            UberSplat::new(
+                Vector3::new(i as f32, 0.0, 0.0),
+                None,
+                Color::SphericalHarmonic(Vector3::new(0.5, 0.0, -0.5), rest),
+                Opacity::LogitFloat(1.0),
+                Scale::Exponent(Vector3::repeat(-(i % 4) as f32)),
+                Quaternion::new(-2.0, 0.0, 0.0, 0.0),
+            )
+        })
+        .collect::<Vec<_>>();
+    let options = VqOptions {
+        sh_codebook: 8,
+        scale_codebook: Some(4),
+        rotation_codebook: None,
+        seed: Some(3),
+        ..Default::default()
+    };
+    let scene = VqScene::encode(&splats, &options).unwrap();
+    assert_eq!(scene.sh.as_ref().unwrap().0.len(), 2);
+    let decoded = scene.decode();
+    let diff = diff_scenes(&splats, &decoded, DiffMatching::Index);
+    for error in diff.attributes {
+        assert!(error.max < 1e-5, "{} {}", error.attribute, error.max);
+    }
+}